@@ -0,0 +1,94 @@
+// A simple computer player. It only looks at what the player is allowed to know,
+// so it can't cheat.
+
+use game::{Card, CardIndex, Game, PlayerIndex, PlayerView, Power};
+use messages::Action;
+
+// Unknown cards are assumed to be worth this much, which is about average.
+const UNKNOWN_CARD_SCORE: u32 = 6;
+// Call kabo once the estimated score of the own cards is this low.
+const KABO_SCORE: u32 = 12;
+// Take the top card of the discard pile if it is at most worth this much.
+const LOW_CARD_SCORE: u32 = 3;
+
+// Picks a reasonable move for the current player: keep low cards, use the powers
+// to learn about cards and call kabo once the own cards seem to be worth little.
+// The returned action is always one of the legal actions of the game. Once the
+// game is over there are none, so there is nothing to choose.
+pub fn choose_action(game: &Game, player_view: &PlayerView) -> Option<Action> {
+    let legal_actions = game.legal_actions();
+    let own_cards = &player_view.cards[player_view.player_index.0 as usize];
+
+    let action = match player_view.hand_card {
+        None => {
+            let low_discard = player_view.discard_top
+                .is_some_and(|card| card.score() <= LOW_CARD_SCORE);
+            if estimated_score(own_cards) <= KABO_SCORE {
+                Action::Kabo
+            } else if low_discard {
+                Action::DiscardDraw
+            } else {
+                Action::DeckDraw
+            }
+        }
+        Some(hand_card) => choose_use(hand_card, own_cards, player_view),
+    };
+
+    if legal_actions.contains(&action) {
+        Some(action)
+    } else {
+        // Drawing is always possible, if anything is. A hand card can always
+        // replace one of the own cards, even if it can't be discarded.
+        legal_actions.into_iter().next()
+    }
+}
+
+// Decides what to do with the hand card.
+fn choose_use(hand_card: Card, own_cards: &[Option<Card>], player_view: &PlayerView) -> Action {
+    let highest_known = own_cards.iter()
+        .enumerate()
+        .filter_map(|(index, card)| card.map(|card| (index, card.score())))
+        .max_by_key(|&(_, score)| score);
+    let first_unknown = own_cards.iter().position(|card| card.is_none());
+
+    if let Some((index, score)) = highest_known {
+        if score > hand_card.score() {
+            return Action::Replace { card_index: CardIndex(index as u8) };
+        }
+    }
+    if let Some(index) = first_unknown {
+        if hand_card.score() <= LOW_CARD_SCORE {
+            return Action::Replace { card_index: CardIndex(index as u8) };
+        }
+    }
+
+    match hand_card.power() {
+        Some(Power::Peek) => {
+            if let Some(index) = first_unknown {
+                return Action::Peek { card_index: CardIndex(index as u8) };
+            }
+        }
+        Some(Power::Spy) => {
+            for (player_index, cards) in player_view.cards.iter().enumerate() {
+                if player_index == player_view.player_index.0 as usize {
+                    continue;
+                }
+                if let Some(index) = cards.iter().position(|card| card.is_none()) {
+                    return Action::Spy {
+                        other_player_index: PlayerIndex(player_index as u8),
+                        card_index: CardIndex(index as u8),
+                    };
+                }
+            }
+        }
+        _ => {}
+    }
+
+    Action::Discard
+}
+
+fn estimated_score(cards: &[Option<Card>]) -> u32 {
+    cards.iter()
+        .map(|card| card.map_or(UNKNOWN_CARD_SCORE, |card| card.score()))
+        .sum()
+}
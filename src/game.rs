@@ -1,114 +1,667 @@
 // This module contains the game state and messages.
 
-use rand::{thread_rng, Rng};
+use rand::{thread_rng, Rng, SeedableRng, StdRng};
+use std::error::Error;
+use std::fmt;
 use std::mem::swap;
+use std::str::FromStr;
+use messages::{Action, Server};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde")]
+use serde_json;
+#[cfg(feature = "serde")]
+use std::convert::TryFrom;
 
 // Types for everything that behaves like an object
 
+// Only kings can be red. The red kings are worth nothing, black kings count 13.
+// Deserialized cards are checked with Card::try_new, like any other client input.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct Card(u8);
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(into = "CardData", try_from = "CardData"))]
+pub struct Card {
+    number: u8,
+    red: bool,
+}
+
+// How a card looks as JSON: just its number, only a red king is written "K",
+// like in Display. What arrives from serde is still unchecked.
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum CardData {
+    Number(u8),
+    Text(String),
+}
+
+// Indices are wrapped, so the compiler notices when they get mixed up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PlayerIndex(pub u8);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CardIndex(pub u8);
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Player {
     name: String,
-    cards: Vec<Card>,
+    cards: Vec<FaceDownCard>,
+}
+
+// A card lying face down in front of a player. It remembers which players know
+// its value, so this knowledge moves along with the card.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+struct FaceDownCard {
+    card: Card,
+    known_by: Vec<PlayerIndex>,
 }
 
-// Before the game starts, each player is allowed to peek twice.
-// Stores how many peeks are left for each player.
+// Before the game starts, each player is allowed to peek at some of their cards,
+// usually twice. Stores how many peeks are left for each player.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct PreGame {
     deck: Vec<Card>,
     discard_pile: Vec<Card>,
     players: Vec<(Player, u8)>,
     total_peeks_left: u8,
+    deck_config: DeckConfig,
+    scoring: ScoringConfig,
+    shuffler: Shuffler,
+}
+
+// Collects the settings for a PreGame. Anything not set falls back to a default:
+// four cards per player, two peeks each, the standard deck and a random seed.
+#[derive(Debug, Clone)]
+pub struct PreGameBuilder {
+    names: Vec<String>,
+    cards_per_player: u8,
+    peeks_per_player: u8,
+    deck_config: DeckConfig,
+    scoring: ScoringConfig,
+    seed: Option<u64>,
+}
+
+// Everything a single player may know about the game. Face-down cards are None,
+// unless the player has legitimately seen them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PlayerView {
+    pub player_index: PlayerIndex,
+    pub discard_top: Option<Card>,
+    pub cards: Vec<Vec<Option<Card>>>,
+    pub hand_card: Option<Card>,
+    pub current_player: PlayerIndex,
+    pub kabo: Option<PlayerIndex>,
+}
+
+// Everything a spectator may know about the game. Unlike PlayerView, this
+// contains no face-down cards at all.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct GameSnapshot {
+    pub discard_pile: Vec<Card>,
+    pub card_counts: Vec<usize>,
+    pub deck_size: usize,
+    pub current_player: PlayerIndex,
+    pub holding_card: bool,
+    pub kabo: Option<PlayerIndex>,
+}
+
+// What a card from the deck can be used for instead of just discarding it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Power {
+    Peek,
+    Spy,
+    Swap,
+}
+
+// How many power cards of each kind a player hasn't seen yet. They are in the
+// deck or face down in front of someone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PowerCounts {
+    pub peek: u16,
+    pub spy: u16,
+    pub swap: u16,
 }
 
+// With serde, the whole state can be saved and loaded again. This includes the
+// order of the deck and all face-down cards, so the saved state must never be
+// sent to a player.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Game {
     deck: Vec<Card>,
     discard_pile: Vec<Card>,
     players: Vec<Player>,
-    current_player: u8,
-    kabo: Option<u8>,
-    hand_card: Option<Card>,
+    // In elimination variants, players who are out are skipped. Being marked
+    // out is no move, so undo leaves these alone.
+    out: Vec<bool>,
+    current_player: PlayerIndex,
+    // Once kabo was called, every other player gets exactly one more turn.
+    kabo: Option<PlayerIndex>,
+    phase: TurnPhase,
+    history: Vec<GameEvent>,
+    // The cards the game was dealt from.
+    deck_config: DeckConfig,
+    scoring: ScoringConfig,
+    shuffler: Shuffler,
+    // The states before the last few moves, the latest one at the end. They
+    // aren't saved. A state which wasn't needed is kept to be overwritten by
+    // the next move, so moves don't allocate a new one.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    undo_stack: Vec<UndoState>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    spare_state: Option<UndoState>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    observer: ObserverSlot,
+}
+
+// The current player first draws a card and then holds it until they decide
+// what to do with it. Once the game is over, nobody can act anymore.
+// A card taken from the discard pile can't be discarded again right away.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+enum TurnPhase {
+    AwaitingDraw,
+    Holding { card: Card, from_discard: bool },
+    Ended,
+}
+
+// What a move can change, kept to undo it. The history only grows during a move,
+// so its length is enough to restore it.
+#[derive(Debug, Clone)]
+struct UndoState {
+    deck: Vec<Card>,
+    discard_pile: Vec<Card>,
+    players: Vec<Player>,
+    current_player: PlayerIndex,
+    kabo: Option<PlayerIndex>,
+    phase: TurnPhase,
+    shuffler: Shuffler,
+    history_len: usize,
+}
+
+// How many cards of each value are in the deck. The count for a value is stored
+// at the index of that value. Of the 13s, up to red_kings are red.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DeckConfig {
+    pub counts: [u8; 14],
+    pub red_kings: u8,
+}
+
+// How the final scores are computed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ScoringConfig {
+    // Added to the score of the kabo caller, unless they have the strictly lowest score.
+    pub kabo_penalty: u32,
+}
+
+const DEFAULT_CARDS_PER_PLAYER: u8 = 4;
+const DEFAULT_PEEKS: u8 = 2;
+// How many moves can be undone.
+const UNDO_DEPTH: usize = 16;
+
+// All randomness of a game comes from the seed, so a game started with the same
+// seed always plays out the same way. Every shuffle uses a generator seeded from
+// the seed and the number of earlier shuffles, so there is no generator state
+// which would have to be saved.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+struct Shuffler {
+    seed: u64,
+    shuffles: u64,
 }
 
-// This should be an associated constant, once that feature stabilizes.
-const DECK_SIZE: usize = 52;
+// Gets told about every move, for example to log a networked game.
+// The events include the private ones. Both methods do nothing by default.
+pub trait Observer {
+    fn on_event(&mut self, _event: &GameEvent) {}
+    fn on_error(&mut self, _error: &GameError) {}
+}
+
+// Holds the observer of a game. Snapshots and clones aren't observed.
+// The observer has to be Send, so the game can still be moved to another thread.
+#[derive(Default)]
+struct ObserverSlot(Option<Box<dyn Observer + Send>>);
 
 impl Card {
     fn new(number: u8) -> Self {
         // Ideally this would be done with a dependent type.
         assert!(number <= 13, "The number {} is no valid card!", number);
 
-        Card(number)
+        Card { number, red: false }
+    }
+    // Like new, but for numbers that come from outside. Anything above 13 is an error.
+    pub fn try_new(number: u8) -> Result<Self, GameError> {
+        if number <= 13 {
+            Ok(Card::new(number))
+        } else {
+            Err(GameError::InvalidCard)
+        }
+    }
+    pub fn red_king() -> Self {
+        Card { number: 13, red: true }
     }
-    fn full_deck() -> Vec<Self> {
-        let mut cards = Vec::with_capacity(DECK_SIZE);
-        cards.push(Card::new(0));
-        cards.push(Card::new(0));
-        for i in 1..13 {
-            for _ in 0..4 {
-                cards.push(Card::new(i));
+    pub fn value(&self) -> u8 {
+        self.number
+    }
+    pub fn is_red_king(&self) -> bool {
+        self.red
+    }
+    // Cards can be stacked when their numbers match, the color doesn't matter.
+    pub fn same_value(&self, other: &Card) -> bool {
+        self.number == other.number
+    }
+    // The 7 to 12 grant a power when they are discarded after drawing.
+    // This is the only place which knows which card has which power.
+    pub fn power(&self) -> Option<Power> {
+        match self.number {
+            7 | 8 => Some(Power::Peek),
+            9 | 10 => Some(Power::Spy),
+            11 | 12 => Some(Power::Swap),
+            _ => None,
+        }
+    }
+    pub fn is_special(&self) -> bool {
+        self.power().is_some()
+    }
+    // Creates a sorted deck, shuffle it before using it.
+    pub fn deck_from_config(config: &DeckConfig) -> Vec<Self> {
+        let mut cards = Vec::with_capacity(config.size());
+        for (number, &count) in config.counts.iter().enumerate() {
+            for _ in 0..count {
+                cards.push(Card::new(number as u8));
             }
         }
-        cards.push(Card::new(13));
-        cards.push(Card::new(13));
+        // The kings come last, so the first red_kings of them are turned red.
+        let kings = config.counts[13].min(config.red_kings) as usize;
+        let first_king = cards.len() - config.counts[13] as usize;
+        for card in &mut cards[first_king..first_king + kings] {
+            *card = Card::red_king();
+        }
 
-        thread_rng().shuffle(&mut cards);
+        cards
+    }
+    fn full_deck(config: &DeckConfig, shuffler: &mut Shuffler) -> Vec<Self> {
+        let mut cards = Card::deck_from_config(config);
+        shuffler.shuffle(&mut cards);
 
-        assert!(cards.len() == DECK_SIZE);
+        assert!(cards.len() == config.size());
         cards
     }
+    pub fn score(&self) -> u32 {
+        if self.red {
+            0
+        } else {
+            self.number as u32
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum PreGameError {
     NoPeeksLeft,
     InvalidIndex,
+    NotEnoughPlayers,
+    TooManyPlayers,
+    TooManyCards,
+    AlreadyPeeked,
+    TooManyPeeks,
 }
 
-impl PreGame {
-    pub fn new(names: Vec<&str>, cards_per_player: u8) -> Self {
-        let mut deck = Card::full_deck();
+// The red 13 is rendered as a king, all other cards just show their number.
+impl fmt::Display for Card {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.red {
+            write!(f, "K")
+        } else {
+            write!(f, "{}", self.number)
+        }
+    }
+}
+
+// Reads what Display writes, so "K" is a red king and "13" a black one.
+impl FromStr for Card {
+    type Err = GameError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        if text == "K" {
+            return Ok(Card::red_king());
+        }
+        text.parse().map_err(|_| GameError::InvalidCard).and_then(Card::try_new)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<CardData> for Card {
+    type Error = GameError;
+
+    fn try_from(data: CardData) -> Result<Self, Self::Error> {
+        match data {
+            CardData::Number(number) => Card::try_new(number),
+            CardData::Text(text) => text.parse(),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<Card> for CardData {
+    fn from(card: Card) -> Self {
+        if card.red {
+            CardData::Text(card.to_string())
+        } else {
+            CardData::Number(card.number)
+        }
+    }
+}
+
+impl From<u8> for PlayerIndex {
+    fn from(index: u8) -> Self {
+        PlayerIndex(index)
+    }
+}
+
+impl From<u8> for CardIndex {
+    fn from(index: u8) -> Self {
+        CardIndex(index)
+    }
+}
+
+impl fmt::Display for PreGameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PreGameError::NoPeeksLeft => write!(f, "the player has no peeks left"),
+            PreGameError::InvalidIndex => write!(f, "there is no card at this index"),
+            PreGameError::NotEnoughPlayers => write!(f, "at least two players are needed"),
+            PreGameError::TooManyPlayers => write!(f, "at most 255 players can play"),
+            PreGameError::TooManyCards => write!(f, "there are not enough cards for this deal"),
+            PreGameError::AlreadyPeeked => write!(f, "the player already knows this card"),
+            PreGameError::TooManyPeeks => write!(f, "there are more peeks than cards"),
+        }
+    }
+}
+
+impl Error for PreGameError {}
+
+impl DeckConfig {
+    // The standard deck has two 0s, four of each value from 1 to 12 and two 13s,
+    // which makes 52 cards. Both 13s are red kings.
+    pub fn standard() -> Self {
+        DeckConfig {
+            counts: [2, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 2],
+            red_kings: 2,
+        }
+    }
+    pub fn size(&self) -> usize {
+        self.counts.iter().map(|&count| count as usize).sum()
+    }
+    // Counts the given cards, so dealing them again gives the same deck.
+    fn from_cards<'a, I>(cards: I) -> Self
+        where I: IntoIterator<Item = &'a Card>
+    {
+        let mut config = DeckConfig {
+            counts: [0; 14],
+            red_kings: 0,
+        };
+        for card in cards {
+            config.counts[card.number as usize] += 1;
+            if card.red {
+                config.red_kings += 1;
+            }
+        }
+
+        config
+    }
+}
+
+impl Default for DeckConfig {
+    fn default() -> Self {
+        DeckConfig::standard()
+    }
+}
+
+impl PowerCounts {
+    fn count_mut(&mut self, power: Power) -> &mut u16 {
+        match power {
+            Power::Peek => &mut self.peek,
+            Power::Spy => &mut self.spy,
+            Power::Swap => &mut self.swap,
+        }
+    }
+}
+
+impl ScoringConfig {
+    pub fn standard() -> Self {
+        ScoringConfig { kabo_penalty: 10 }
+    }
+}
+
+impl Default for ScoringConfig {
+    fn default() -> Self {
+        ScoringConfig::standard()
+    }
+}
+
+impl Shuffler {
+    fn new(seed: u64) -> Self {
+        Shuffler { seed, shuffles: 0 }
+    }
+    fn shuffle(&mut self, cards: &mut [Card]) {
+        let seed = [(self.seed >> 32) as usize, self.seed as usize, self.shuffles as usize];
+        StdRng::from_seed(&seed).shuffle(cards);
+        self.shuffles += 1;
+    }
+}
+
+impl ObserverSlot {
+    fn event(&mut self, event: &GameEvent) {
+        if let Some(ref mut observer) = self.0 {
+            observer.on_event(event);
+        }
+    }
+    fn error(&mut self, error: &GameError) {
+        if let Some(ref mut observer) = self.0 {
+            observer.on_error(error);
+        }
+    }
+}
+
+// The observer stays with the original game, the clone starts without one.
+impl Clone for ObserverSlot {
+    fn clone(&self) -> Self {
+        ObserverSlot(None)
+    }
+}
+
+impl fmt::Debug for ObserverSlot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            Some(_) => write!(f, "Observed"),
+            None => write!(f, "Unobserved"),
+        }
+    }
+}
+
+impl PreGameBuilder {
+    pub fn new() -> Self {
+        PreGameBuilder {
+            names: vec![],
+            cards_per_player: DEFAULT_CARDS_PER_PLAYER,
+            peeks_per_player: DEFAULT_PEEKS,
+            deck_config: DeckConfig::standard(),
+            scoring: ScoringConfig::standard(),
+            seed: None,
+        }
+    }
+    // Players take their turns in the order they were added.
+    pub fn player(mut self, name: &str) -> Self {
+        self.names.push(name.to_owned());
+        self
+    }
+    pub fn cards_per_player(mut self, cards_per_player: u8) -> Self {
+        self.cards_per_player = cards_per_player;
+        self
+    }
+    pub fn peeks(mut self, peeks_per_player: u8) -> Self {
+        self.peeks_per_player = peeks_per_player;
+        self
+    }
+    pub fn deck(mut self, deck_config: DeckConfig) -> Self {
+        self.deck_config = deck_config;
+        self
+    }
+    pub fn scoring(mut self, scoring: ScoringConfig) -> Self {
+        self.scoring = scoring;
+        self
+    }
+    // With a seed, the deck is shuffled deterministically.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+    // Moves the seed on by the number of the round, so every round of a match
+    // is dealt differently. Without a seed, every deal is random anyway.
+    pub fn for_round(mut self, round: u64) -> Self {
+        self.seed = self.seed.map(|seed| seed.wrapping_add(round));
+        self
+    }
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+    // Checks the settings and deals the cards.
+    pub fn build(self) -> Result<PreGame, PreGameError> {
+        let PreGameBuilder {
+            names,
+            cards_per_player,
+            peeks_per_player,
+            deck_config,
+            scoring,
+            seed,
+        } = self;
+        if names.len() < 2 {
+            return Err(PreGameError::NotEnoughPlayers);
+        }
+        // Player indices are a single byte.
+        if names.len() > u8::MAX as usize {
+            return Err(PreGameError::TooManyPlayers);
+        }
+        // One card is needed to start the discard pile.
+        if names.len() * cards_per_player as usize + 1 > deck_config.size() {
+            return Err(PreGameError::TooManyCards);
+        }
+        // Each card can only be peeked at once.
+        if peeks_per_player > cards_per_player {
+            return Err(PreGameError::TooManyPeeks);
+        }
+        let total_peeks_left = (names.len() as u8)
+            .checked_mul(peeks_per_player)
+            .ok_or(PreGameError::TooManyPeeks)?;
+
+        let mut shuffler = Shuffler::new(seed.unwrap_or_else(|| thread_rng().gen()));
+        let mut deck = Card::full_deck(&deck_config, &mut shuffler);
         let first_card = deck.pop().unwrap();
         let discard_pile = vec![first_card];
 
         let mut players = Vec::with_capacity(names.len());
-        for name_borrow in names {
-            let name: String = name_borrow.to_owned();
+        for name in names {
             let drain_index = deck.len() - cards_per_player as usize;
             let cards: Vec<Card> = deck.drain(drain_index..).collect();
             assert!(cards.len() == cards_per_player as usize);
 
-            players.push((Player::new(name, cards), 2));
+            players.push((Player::new(name, cards), peeks_per_player));
         }
 
-        PreGame {
+        Ok(PreGame {
             deck,
             discard_pile,
-            total_peeks_left: players.len() as u8 * 2,
+            total_peeks_left,
             players,
+            deck_config,
+            scoring,
+            shuffler,
+        })
+    }
+}
+
+impl Default for PreGameBuilder {
+    fn default() -> Self {
+        PreGameBuilder::new()
+    }
+}
+
+impl PreGame {
+    pub fn builder() -> PreGameBuilder {
+        PreGameBuilder::new()
+    }
+    // Without a deck config, the standard deck is used.
+    pub fn new(names: Vec<&str>,
+               cards_per_player: u8,
+               deck_config: Option<&DeckConfig>)
+               -> Result<Self, PreGameError> {
+        PreGame::new_seeded(names, cards_per_player, deck_config, thread_rng().gen())
+    }
+    // Like new, but the deck is shuffled deterministically from the seed.
+    pub fn new_seeded(names: Vec<&str>,
+                      cards_per_player: u8,
+                      deck_config: Option<&DeckConfig>,
+                      seed: u64)
+                      -> Result<Self, PreGameError> {
+        PreGame::with_peeks(names, cards_per_player, deck_config, DEFAULT_PEEKS, seed)
+    }
+    // Like new_seeded, but each player may peek peeks_per_player times.
+    pub fn with_peeks(names: Vec<&str>,
+                      cards_per_player: u8,
+                      deck_config: Option<&DeckConfig>,
+                      peeks_per_player: u8,
+                      seed: u64)
+                      -> Result<Self, PreGameError> {
+        let mut builder = PreGame::builder()
+            .cards_per_player(cards_per_player)
+            .peeks(peeks_per_player)
+            .deck(deck_config.cloned().unwrap_or_default())
+            .seed(seed);
+        for name in names {
+            builder = builder.player(name);
         }
+        builder.build()
     }
     // Reveals to the player what card is hidden at a given location.
     // If they have no peeks left, it returns an error.
     // If they ask about an invalid index, it returns an error.
+    // If they already peeked at this card, it returns an error without using up a peek.
     // If an invalid player_index is supplied, it PANICS.
     // (because this value is not supplied by the client)
-    pub fn peek(&mut self, player_index: u8, card_index: u8) -> Result<Card, PreGameError> {
-        let ref mut player_tuple = self.players
-            .get_mut(player_index as usize)
+    pub fn peek(&mut self,
+                player_index: PlayerIndex,
+                card_index: CardIndex)
+                -> Result<Card, PreGameError> {
+        let player_tuple = self.players
+            .get_mut(player_index.0 as usize)
             .expect("Invalid player index.");
-        let ref player = player_tuple.0;
-        let ref mut peeks_left = player_tuple.1;
+        let player = &mut player_tuple.0;
+        let peeks_left = &mut player_tuple.1;
 
         if *peeks_left > 0 {
+            if let Some(face_down_card) = player.cards.get(card_index.0 as usize) {
+                if face_down_card.is_known_by(player_index) {
+                    return Err(PreGameError::AlreadyPeeked);
+                }
+            }
+
             *peeks_left -= 1;
             self.total_peeks_left -= 1;
-            if let Some(card) = player.cards.get(card_index as usize) {
-                Ok(card.clone())
+            if let Some(face_down_card) = player.cards.get_mut(card_index.0 as usize) {
+                face_down_card.reveal_to(player_index);
+                Ok(face_down_card.card)
             } else {
                 Err(PreGameError::InvalidIndex)
             }
@@ -116,40 +669,105 @@ impl PreGame {
             Err(PreGameError::NoPeeksLeft)
         }
     }
+    // How many peeks this player still has to use.
+    // If an invalid player_index is supplied, it PANICS, like peek.
+    pub fn peeks_left(&self, player_index: PlayerIndex) -> u8 {
+        self.players
+            .get(player_index.0 as usize)
+            .expect("Invalid player index.")
+            .1
+    }
+    pub fn total_peeks_left(&self) -> u8 {
+        self.total_peeks_left
+    }
+    // Once every peek is used up, to_game can be called without panicking.
+    pub fn ready(&self) -> bool {
+        self.total_peeks_left == 0
+    }
     pub fn to_game(mut self) -> Game {
-        assert!(self.total_card_amout() == DECK_SIZE);
-        assert!(self.total_peeks_left == 0);
-        for ref player in &self.players {
+        // The expected total comes from the deck config, so custom decks work too.
+        assert!(self.verify_deck_composition(),
+                "Cards were created or destroyed.");
+        assert!(self.ready(), "Not all peeks were used.");
+        for player in &self.players {
             // Double check that there aren't any peeks left here either.
             assert!(player.1 == 0);
         }
 
-        let players = self.players.drain(..).map(|x| x.0).collect();
+        let players: Vec<Player> = self.players.drain(..).map(|x| x.0).collect();
 
         Game {
             deck: self.deck,
             discard_pile: self.discard_pile,
-            players: players,
-            current_player: 0,
+            out: vec![false; players.len()],
+            players,
+            current_player: PlayerIndex(0),
             kabo: None,
-            hand_card: None,
+            phase: TurnPhase::AwaitingDraw,
+            history: vec![GameEvent::StartTurn { player_index: PlayerIndex(0) }],
+            deck_config: self.deck_config,
+            scoring: self.scoring,
+            shuffler: self.shuffler,
+            undo_stack: vec![],
+            spare_state: None,
+            observer: ObserverSlot::default(),
         }
     }
-    // A test to assert that the number of cards doesn't change unexpectedly.
-    fn total_card_amout(&self) -> usize {
-        let mut total = self.deck.len() + self.discard_pile.len();
-        for ref player in &self.players {
-            total += player.0.cards.len()
-        }
+    // Checks that the deck, the discard pile and the hands together hold exactly
+    // the cards of the deck config, every value as often as configured.
+    pub fn verify_deck_composition(&self) -> bool {
+        let hands = self.players
+            .iter()
+            .flat_map(|player| player.0.cards.iter().map(|card| &card.card));
+        let cards = self.deck.iter().chain(&self.discard_pile).chain(hands);
+        let counted = DeckConfig::from_cards(cards);
+        // A config may ask for more red kings than it has kings.
+        let red_kings = self.deck_config.red_kings.min(self.deck_config.counts[13]);
 
-        total
+        counted.counts == self.deck_config.counts && counted.red_kings == red_kings
     }
 }
 
 impl Player {
     fn new(name: String, cards: Vec<Card>) -> Self {
+        let cards = cards.into_iter().map(FaceDownCard::new).collect();
         Player { name, cards }
     }
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    // The values of the cards are hidden, use Game::view_for to get them.
+    pub fn card_count(&self) -> usize {
+        self.cards.len()
+    }
+    fn score(&self) -> u32 {
+        self.cards.iter().map(|face_down_card| face_down_card.card.score()).sum()
+    }
+}
+
+impl FaceDownCard {
+    fn new(card: Card) -> Self {
+        FaceDownCard {
+            card,
+            known_by: vec![],
+        }
+    }
+    fn reveal_to(&mut self, player_index: PlayerIndex) {
+        if !self.is_known_by(player_index) {
+            self.known_by.push(player_index);
+        }
+    }
+    fn is_known_by(&self, player_index: PlayerIndex) -> bool {
+        self.known_by.contains(&player_index)
+    }
+    // The card, if the player is allowed to know it.
+    fn seen_by(&self, player_index: PlayerIndex) -> Option<Card> {
+        if self.is_known_by(player_index) {
+            Some(self.card)
+        } else {
+            None
+        }
+    }
 }
 
 macro_rules! ensure {
@@ -160,140 +778,1075 @@ macro_rules! ensure {
     }};
 }
 
-type Status = Result<Vec<GameEvent>, GameError>;
+pub type Status = Result<Vec<GameEvent>, GameError>;
+// Some methods push their events into a buffer instead of returning them.
+type Pushed = Result<(), GameError>;
+
+// The outcome of a successful action, see Game::apply.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActionResult {
+    pub player_index: PlayerIndex,
+    pub action: Action,
+    pub events: Vec<GameEvent>,
+    pub discards: Vec<Card>,
+}
+
+impl ActionResult {
+    pub fn new(player_index: PlayerIndex, action: Action, events: Vec<GameEvent>) -> Self {
+        let mut discards = vec![];
+        for event in &events {
+            if let GameEvent::Discards { ref cards } = *event {
+                discards.extend(cards);
+            }
+        }
+
+        ActionResult {
+            player_index,
+            action,
+            events,
+            discards,
+        }
+    }
+    // The message which tells everyone about this action.
+    pub fn success_message(&self) -> Server {
+        Server::ActionSuccess {
+            player_index: self.player_index,
+            action: self.action.clone(),
+            discards: self.discards.clone(),
+        }
+    }
+}
 
+// Events are returned in the order they happen. Using a power card first
+// produces the Seen or Swapped event, then Discards for the power card itself
+// and finally EndTurn followed by StartTurn, or GameOver.
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum GameEvent {
     DiscardShuffle,
     Discards { cards: Vec<Card> },
-    Kabo { player_index: u8 },
-    EndTurn { next_player: u8 },
+    Kabo { player_index: PlayerIndex },
+    EndTurn { next_player: PlayerIndex },
+    // Also the first event of every game, for the player who starts.
+    StartTurn { player_index: PlayerIndex },
+    // The current player learns the card they drew. Like Seen, this is private.
+    CardDrawn { recipient: PlayerIndex, card: Card },
+    // Only the recipient may learn the card, it must not be sent to anyone else.
     Seen {
-        player_index: u8,
-        card_index: u8,
+        recipient: PlayerIndex,
+        player_index: PlayerIndex,
+        card_index: CardIndex,
+        card: Card,
+    },
+    Swapped {
+        player_index: PlayerIndex,
+        card_index: CardIndex,
+        other_player_index: PlayerIndex,
+        other_card_index: CardIndex,
+    },
+    MultiReplaceFailure {
+        player_index: PlayerIndex,
+        card_type_claimed: Card,
+        cards_seen: Vec<(CardIndex, Card)>,
+    },
+    SnapFailure {
+        player_index: PlayerIndex,
+        card_index: CardIndex,
         card: Card,
     },
     GameOver,
 }
 
+// Who may learn about an event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Visibility {
+    Public,
+    Private { recipient: PlayerIndex },
+}
+
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum GameError {
     WrongPhase,
-    AlreadyKabo { player_index: u8 },
+    AlreadyKabo { player_index: PlayerIndex },
     WrongCard,
     InvalidIndex,
+    NotYourTurn { expected: PlayerIndex, got: PlayerIndex },
+    EmptyDiscard,
+    GameFinished,
+    OutOfCards,
+    MalformedMessage,
+    MustReplace,
+    NothingToUndo,
+    InvalidCard,
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            GameError::WrongPhase => write!(f, "it is not the correct phase for this action"),
+            GameError::AlreadyKabo { player_index } => {
+                write!(f, "player {} has already called kabo", player_index.0)
+            }
+            GameError::WrongCard => write!(f, "the hand card can't be used for this action"),
+            GameError::InvalidIndex => write!(f, "the selected card or player is not valid"),
+            GameError::NotYourTurn { expected, got } => {
+                write!(f, "it is the turn of player {}, not of player {}", expected.0, got.0)
+            }
+            GameError::EmptyDiscard => write!(f, "the discard pile is empty"),
+            GameError::GameFinished => write!(f, "the game is already over"),
+            GameError::OutOfCards => write!(f, "there are no cards left to draw"),
+            GameError::MalformedMessage => write!(f, "the message could not be understood"),
+            GameError::MustReplace => {
+                write!(f, "a card taken from the discard pile must replace one of your cards")
+            }
+            GameError::NothingToUndo => write!(f, "there is no move to undo"),
+            GameError::InvalidCard => write!(f, "there is no such card"),
+        }
+    }
+}
+
+impl Error for GameError {}
+
+impl GameEvent {
+    // Only CardDrawn and Seen reveal a card to a single player, everything else
+    // may be shown to all players and spectators.
+    pub fn visibility(&self) -> Visibility {
+        match *self {
+            GameEvent::CardDrawn { recipient, .. } |
+            GameEvent::Seen { recipient, .. } => Visibility::Private { recipient },
+            _ => Visibility::Public,
+        }
+    }
+}
+
+// Building blocks for tests which need a game with known cards. These skip the
+// shuffling and the peeks, so they are only available with the fixtures feature.
+#[cfg(feature = "fixtures")]
+impl Card {
+    pub fn from_value(number: u8) -> Self {
+        Card::new(number)
+    }
 }
 
+#[cfg(feature = "fixtures")]
+impl Game {
+    // The last card of the deck is drawn first and the last discard is on top.
+    // Nobody knows any of the face-down cards and player 0 starts.
+    pub fn from_parts(players: Vec<(String, Vec<Card>)>,
+                      deck: Vec<Card>,
+                      discard_pile: Vec<Card>)
+                      -> Game {
+        let players: Vec<Player> = players.into_iter()
+            .map(|(name, cards)| Player::new(name, cards))
+            .collect();
+        let hands = players.iter().flat_map(|player| player.cards.iter().map(|card| &card.card));
+        let deck_config = DeckConfig::from_cards(deck.iter().chain(&discard_pile).chain(hands));
+
+        Game {
+            deck,
+            discard_pile,
+            out: vec![false; players.len()],
+            players,
+            current_player: PlayerIndex(0),
+            kabo: None,
+            phase: TurnPhase::AwaitingDraw,
+            history: vec![GameEvent::StartTurn { player_index: PlayerIndex(0) }],
+            deck_config,
+            scoring: ScoringConfig::standard(),
+            shuffler: Shuffler::new(0),
+            undo_stack: vec![],
+            spare_state: None,
+            observer: ObserverSlot::default(),
+        }
+    }
+}
+
+// Saving and loading. The JSON contains every secret of the game, keep it on the server.
+#[cfg(feature = "serde")]
+impl Game {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("A game can always be serialized.")
+    }
+    // The moves before the save can't be undone after loading.
+    pub fn from_json(json: &str) -> Result<Game, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+}
 
 #[allow(dead_code)]
 impl Game {
-    // Draw a card from the deck. If there is none, shuffle the discard pile as new deck.
-    // The functions return Err(()) if the move is illegal, but have no way to check if they
-    // were send by the corret player. This has to be verified externally.
-    pub fn deck_draw(&mut self) -> Status {
-        ensure!(self.hand_card.is_none(), GameError::WrongPhase);
+    // Executes an action for the given player. This is the only method which checks
+    // that the action was sent by the current player, so prefer it over calling the
+    // individual methods below directly.
+    // Asking for the state doesn't change the game and is handled by view_for instead.
+    pub fn apply(&mut self,
+                 player_index: PlayerIndex,
+                 action: Action)
+                 -> Result<ActionResult, GameError> {
+        let mut events = vec![];
+        self.apply_into(player_index, action.clone(), &mut events)?;
 
-        if let Some(card) = self.deck.pop() {
-            self.hand_card = Some(card);
-            Ok(vec![])
-        } else {
-            // The deck is empty, shuffle the discard pile
-            assert!(self.discard_pile.len() >= 4);
-            swap(&mut self.deck, &mut self.discard_pile);
-            thread_rng().shuffle(&mut self.deck);
-            // Yes, there is no remaining top card, but after drawing the player
-            // is certain to discard a card.
+        Ok(ActionResult::new(player_index, action, events))
+    }
+    // Like apply, but the events are appended to a buffer of the caller, which
+    // can be reused for many actions. If the action fails, the buffer is unchanged.
+    pub fn apply_into(&mut self,
+                      player_index: PlayerIndex,
+                      action: Action,
+                      events: &mut Vec<GameEvent>)
+                      -> Pushed {
+        if let Err(error) = self.check_action(player_index, &action) {
+            self.observer.error(&error);
+            return Err(error);
+        }
 
-            self.hand_card = self.deck.pop();
-            Ok(vec![GameEvent::DiscardShuffle])
+        self.record_into(events, |game, events| match action {
+            Action::DeckDraw => game.deck_draw_into(events),
+            Action::DiscardDraw => game.discard_draw_into(events),
+            Action::Kabo => game.announce_kabo_into(player_index, events),
+            Action::Replace { card_index } => game.replace_into(player_index, card_index, events),
+            // The claimed card type is always the value of the hand card.
+            Action::MultiReplace { card_indices, .. } => {
+                game.multi_replace_into(player_index, card_indices, events)
+            }
+            Action::Peek { card_index } => game.peek_into(player_index, card_index, events),
+            Action::Spy {
+                other_player_index,
+                card_index,
+            } => game.spy_into(other_player_index, card_index, events),
+            Action::Swap {
+                my_card_index,
+                other_player_index,
+                other_card_index,
+            } => game.swap_into(my_card_index, other_player_index, other_card_index, events),
+            Action::Discard => game.discard_into(events),
+            Action::Snap { card_index } => game.snap_into(player_index, card_index, events),
+        })
+    }
+    // From now on, the observer is told about every event and error of a move.
+    // It replaces the previous observer, if there was one.
+    pub fn set_observer(&mut self, observer: Box<dyn Observer + Send>) {
+        self.observer = ObserverSlot(Some(observer));
+    }
+    pub fn remove_observer(&mut self) -> Option<Box<dyn Observer + Send>> {
+        self.observer.0.take()
+    }
+    // Applies a list of actions, each with the player who sent it. Stops at the
+    // first action which fails. Starting from a seeded game, the result is
+    // always the same. All actions share one event buffer.
+    pub fn replay(mut self, actions: &[(PlayerIndex, Action)]) -> Result<Game, GameError> {
+        let mut events = vec![];
+        for &(player_index, ref action) in actions {
+            events.clear();
+            self.apply_into(player_index, action.clone(), &mut events)?;
         }
+
+        Ok(self)
     }
-    pub fn discard_draw(&mut self) -> Status {
-        ensure!(self.hand_card.is_none(), GameError::WrongPhase);
+    // Lets another player start, for example to rotate the first player between
+    // rounds. This is only possible before the first move. Drawing leaves no public
+    // event, so the phase has to be checked as well.
+    pub fn set_first_player(&mut self, player_index: PlayerIndex) -> Result<(), GameError> {
+        ensure!(self.history.len() == 1 && self.phase == TurnPhase::AwaitingDraw,
+                GameError::WrongPhase);
+        ensure!((player_index.0 as usize) < self.players.len(),
+                GameError::InvalidIndex);
 
-        // This unwraps an Option<Card> and then it immediately rewraps it.
-        // I do this to assert that there really is a card. If for some reason
-        // the discard pile doesn't contain any cards, then this will panic.
-        // It also is more clear that we are dealing with an Option value.
-        self.hand_card = Some(self.discard_pile.pop().unwrap());
-        Ok(vec![])
+        self.current_player = player_index;
+        self.history = vec![GameEvent::StartTurn { player_index }];
+        Ok(())
     }
-    #[allow(dead_code)]
-    pub fn announce_kabo(&mut self) -> Status {
-        ensure!(self.hand_card.is_none(), GameError::WrongPhase);
-        if let Some(player_index) = self.kabo {
-            return Err(GameError::AlreadyKabo { player_index });
-        };
+    // For elimination variants: the player is skipped from now on, for example
+    // because they have no cards left. If only one player remains, the game
+    // ends with the current turn. In the standard rules, nobody is ever out.
+    pub fn mark_out(&mut self, player_index: PlayerIndex) -> Result<(), GameError> {
+        ensure!(self.phase != TurnPhase::Ended, GameError::GameFinished);
+        let out = self.out
+            .get_mut(player_index.0 as usize)
+            .ok_or(GameError::InvalidIndex)?;
 
-        self.kabo = Some(self.current_player);
-        let kabo_event = GameEvent::Kabo { player_index: self.current_player };
-        Ok(vec![kabo_event, self.end_turn()])
+        *out = true;
+        Ok(())
     }
-    pub fn discard(&mut self) -> Status {
-        ensure!(self.hand_card.is_some(), GameError::WrongPhase);
+    // If an invalid player_index is supplied, it PANICS.
+    pub fn is_out(&self, player_index: PlayerIndex) -> bool {
+        self.out[player_index.0 as usize]
+    }
+    // Restores the state before the last successful move, including the history.
+    // Only the last few moves are kept, older ones can't be undone.
+    pub fn undo(&mut self) -> Result<(), GameError> {
+        let mut previous = self.undo_stack.pop().ok_or(GameError::NothingToUndo)?;
+        swap(&mut self.deck, &mut previous.deck);
+        swap(&mut self.discard_pile, &mut previous.discard_pile);
+        swap(&mut self.players, &mut previous.players);
+        self.current_player = previous.current_player;
+        self.kabo = previous.kabo;
+        self.phase = previous.phase;
+        self.shuffler = previous.shuffler.clone();
+        self.history.truncate(previous.history_len);
+        // The memory of the undone state can be used by the next move.
+        self.spare_state = Some(previous);
 
-        Ok(self.discard_and_end())
+        Ok(())
+    }
+    // Draw a card from the deck. If there is none, shuffle the discard pile as new deck.
+    // The functions return Err(()) if the move is illegal, but have no way to check if they
+    // were send by the corret player. This has to be verified externally or by using apply.
+    pub fn deck_draw(&mut self) -> Status {
+        self.collect(|game, events| game.deck_draw_into(events))
+    }
+    pub fn discard_draw(&mut self) -> Status {
+        self.collect(|game, events| game.discard_draw_into(events))
+    }
+    // Only the current player may call kabo, even without going through apply.
+    pub fn announce_kabo(&mut self, player_index: PlayerIndex) -> Status {
+        self.collect(|game, events| game.announce_kabo_into(player_index, events))
+    }
+    pub fn discard(&mut self) -> Status {
+        self.collect(|game, events| game.discard_into(events))
     }
+    // For timeouts: discards whatever the current player holds and ends their turn.
+    // Unlike discard, this also works for a card taken from the discard pile, nobody
+    // has to wait for a player who doesn't act.
+    pub fn force_discard(&mut self) -> Status {
+        self.collect(|game, events| {
+            game.hand_card()?;
+            game.discard_and_end(events);
+            Ok(())
+        })
+    }
+    // Players may only replace their own cards.
     #[allow(dead_code)]
-    pub fn replace(&mut self, player_index: u8, card_index: u8) -> Status {
-        ensure!(self.hand_card.is_some(), GameError::WrongPhase);
+    pub fn replace(&mut self, player_index: PlayerIndex, card_index: CardIndex) -> Status {
+        self.collect(|game, events| game.replace_into(player_index, card_index, events))
+    }
+    // Replace several face-down cards at once. The player claims that all cards at
+    // the given indices have the same value as the hand card. If the claim holds,
+    // the hand card takes the place of the first card and all claimed cards are
+    // discarded. If it doesn't, the claimed cards are revealed to everyone, the hand
+    // card is discarded and the player has to draw a penalty card from the deck.
+    pub fn multi_replace(&mut self,
+                         player_index: PlayerIndex,
+                         card_indices: Vec<CardIndex>)
+                         -> Status {
+        self.collect(|game, events| game.multi_replace_into(player_index, card_indices, events))
+    }
+    // Use a 7 or 8 to look at one of your own cards. The Seen event is the only
+    // place where the card is revealed, so it must only be sent to this player.
+    pub fn peek(&mut self, player_index: PlayerIndex, card_index: CardIndex) -> Status {
+        self.collect(|game, events| game.peek_into(player_index, card_index, events))
+    }
+    // Use a 9 or 10 to look at a card of another player. Just like with peek,
+    // the Seen event must only be sent to the current player.
+    pub fn spy(&mut self, other_player_index: PlayerIndex, card_index: CardIndex) -> Status {
+        self.collect(|game, events| game.spy_into(other_player_index, card_index, events))
+    }
+    // Use an 11 or 12 to blindly swap one of your cards with a card of another
+    // player. Nobody gets to see the swapped cards.
+    pub fn swap(&mut self,
+                my_card_index: CardIndex,
+                other_player_index: PlayerIndex,
+                other_card_index: CardIndex)
+                -> Status {
+        self.collect(|game, events| {
+            game.swap_into(my_card_index, other_player_index, other_card_index, events)
+        })
+    }
+    // Any player may throw one of their cards onto the discard pile at any time,
+    // if it has the same value as the top card. The turn doesn't change.
+    // A wrong snap shows the card to everyone, it stays in the hand and the
+    // player gets a penalty card.
+    pub fn snap(&mut self, player_index: PlayerIndex, card_index: CardIndex) -> Status {
+        self.collect(|game, events| game.snap_into(player_index, card_index, events))
+    }
+    // Lists the actions the current player may take right now.
+    // Stacks of more than two cards are legal as well, but only pairs are listed
+    // for MultiReplace to keep this list reasonably short.
+    pub fn legal_actions(&self) -> Vec<Action> {
+        let mut actions = vec![];
+        let own_cards = self.players[self.current_player.0 as usize].cards.len() as u8;
 
-        {
-            // Enclose all references to self in an environment to satisfy
-            // the borrow checker.
-            let hand_card = self.hand_card.as_mut().unwrap();
+        match self.phase {
+            TurnPhase::AwaitingDraw => {
+                actions.push(Action::DeckDraw);
+                if !self.discard_pile.is_empty() {
+                    actions.push(Action::DiscardDraw);
+                }
+                if self.can_call_kabo() {
+                    actions.push(Action::Kabo);
+                }
+            }
+            TurnPhase::Holding { card, from_discard } => {
+                if !from_discard || own_cards == 0 {
+                    actions.push(Action::Discard);
+                }
+                for card_index in 0..own_cards {
+                    actions.push(Action::Replace { card_index: CardIndex(card_index) });
+                }
+                for first in 0..own_cards {
+                    for second in (first + 1)..own_cards {
+                        actions.push(Action::MultiReplace {
+                            card_type: card,
+                            card_indices: vec![CardIndex(first), CardIndex(second)],
+                        });
+                    }
+                }
+                // Powers are only available for cards drawn from the deck.
+                match card.power() {
+                    _ if from_discard => {}
+                    Some(Power::Peek) => {
+                        for card_index in 0..own_cards {
+                            actions.push(Action::Peek { card_index: CardIndex(card_index) });
+                        }
+                    }
+                    Some(Power::Spy) => {
+                        for (other_player_index, other_cards) in self.other_card_counts() {
+                            for card_index in 0..other_cards {
+                                actions.push(Action::Spy {
+                                    other_player_index,
+                                    card_index: CardIndex(card_index),
+                                });
+                            }
+                        }
+                    }
+                    Some(Power::Swap) => {
+                        for (other_player_index, other_cards) in self.other_card_counts() {
+                            for my_card_index in 0..own_cards {
+                                for other_card_index in 0..other_cards {
+                                    actions.push(Action::Swap {
+                                        my_card_index: CardIndex(my_card_index),
+                                        other_player_index,
+                                        other_card_index: CardIndex(other_card_index),
+                                    });
+                                }
+                            }
+                        }
+                    }
+                    None => {}
+                }
+            }
+            TurnPhase::Ended => {}
+        }
 
-            if let Some(face_down_card) =
-                self.players[player_index as usize]
-                    .cards
-                    .get_mut(card_index as usize) {
+        actions
+    }
+    // All public events of this game so far. Seen events are left out, because
+    // they reveal cards to a single player only.
+    pub fn history(&self) -> &[GameEvent] {
+        &self.history
+    }
+    pub fn players(&self) -> &[Player] {
+        &self.players
+    }
+    pub fn current_player(&self) -> PlayerIndex {
+        self.current_player
+    }
+    pub fn kabo_caller(&self) -> Option<PlayerIndex> {
+        self.kabo
+    }
+    // Whether the current player may call kabo right now. This is exactly when
+    // announce_kabo would succeed for them.
+    pub fn can_call_kabo(&self) -> bool {
+        self.check_kabo().is_ok()
+    }
+    // After kabo was called, everyone else gets one last turn until the game ends.
+    pub fn in_final_round(&self) -> bool {
+        self.kabo.is_some() && self.phase != TurnPhase::Ended
+    }
+    // The value of the hand card is only visible to the current player, see view_for.
+    pub fn is_holding_card(&self) -> bool {
+        self.hand_card().is_ok()
+    }
+    // Right after the discard pile was shuffled into the deck, there is no top card.
+    pub fn discard_top(&self) -> Option<Card> {
+        self.discard_pile.last().cloned()
+    }
+    pub fn deck_size(&self) -> usize {
+        self.deck.len()
+    }
+    // How many face-down cards each player has. Only stacks, snaps and penalty
+    // cards change these numbers.
+    pub fn player_card_counts(&self) -> Vec<usize> {
+        self.players.iter().map(|player| player.card_count()).collect()
+    }
+    // Returns what the given player is allowed to know about the game.
+    // If an invalid player_index is supplied, it PANICS.
+    pub fn view_for(&self, player_index: PlayerIndex) -> PlayerView {
+        assert!((player_index.0 as usize) < self.players.len(),
+                "Invalid player index.");
 
-                swap(hand_card, face_down_card);
-            } else {
-                return Err(GameError::InvalidIndex);
-            };
+        let cards = self.players
+            .iter()
+            .map(|player| {
+                player.cards
+                    .iter()
+                    .map(|face_down_card| face_down_card.seen_by(player_index))
+                    .collect()
+            })
+            .collect();
+        let hand_card = match self.phase {
+            TurnPhase::Holding { card, .. } if player_index == self.current_player => Some(card),
+            _ => None,
+        };
+
+        PlayerView {
+            player_index,
+            discard_top: self.discard_top(),
+            cards,
+            hand_card,
+            current_player: self.current_player,
+            kabo: self.kabo,
+        }
+    }
+    // Tells a player one of their own cards again, if they have seen it before.
+    // This is not an action, nothing about the game changes.
+    pub fn known_card(&self, player_index: PlayerIndex, card_index: CardIndex) -> Option<Card> {
+        self.card_ref(player_index, card_index)
+            .ok()
+            .and_then(|face_down_card| face_down_card.seen_by(player_index))
+    }
+    // Counts the power cards of the deck config which the player hasn't seen, using
+    // only the discard pile and what the player knows. Cards which were shuffled
+    // back into the deck count as unseen again.
+    // If an invalid player_index is supplied, it PANICS.
+    pub fn power_card_estimate(&self, player_index: PlayerIndex) -> PowerCounts {
+        let mut unseen = PowerCounts {
+            peek: 0,
+            spy: 0,
+            swap: 0,
+        };
+        for (number, &count) in self.deck_config.counts.iter().enumerate() {
+            if let Some(power) = Card::new(number as u8).power() {
+                // Two full counts of 255 don't fit into a byte.
+                *unseen.count_mut(power) += count as u16;
+            }
         }
 
-        Ok(self.discard_and_end())
+        let known = self.players
+            .iter()
+            .flat_map(|player| player.cards.iter())
+            .filter_map(|face_down_card| face_down_card.seen_by(player_index));
+        let hand_card = self.view_for(player_index).hand_card;
+        for card in self.discard_pile.iter().cloned().chain(known).chain(hand_card) {
+            if let Some(power) = card.power() {
+                *unseen.count_mut(power) -= 1;
+            }
+        }
 
+        unseen
     }
-    pub fn multi_replace(&mut self, player_index: u8, card_indices: Vec<u8>) -> Status {
-        ensure!(card_indices.len() >= 2, GameError::InvalidIndex);
-        ensure!(self.hand_card.is_some(), GameError::WrongPhase);
+    // Returns what a neutral observer is allowed to know about the game.
+    pub fn snapshot(&self) -> GameSnapshot {
+        GameSnapshot {
+            discard_pile: self.discard_pile.clone(),
+            card_counts: self.player_card_counts(),
+            deck_size: self.deck.len(),
+            current_player: self.current_player,
+            holding_card: self.is_holding_card(),
+            kabo: self.kabo,
+        }
+    }
+    // Counts all cards in the deck, the discard pile, in front of the players
+    // and in the hand of the current player.
+    pub fn total_cards(&self) -> usize {
+        let mut total = self.deck.len() + self.discard_pile.len();
+        for player in &self.players {
+            total += player.cards.len()
+        }
+        if let TurnPhase::Holding { .. } = self.phase {
+            total += 1;
+        }
 
-        unimplemented!()
+        total
+    }
+    // Asserts that the number of cards doesn't change unexpectedly.
+    // This is checked after every move, but only in debug builds.
+    pub fn check_invariants(&self) {
+        debug_assert!(self.total_cards() == self.deck_config.size(),
+                      "Cards were created or destroyed.");
     }
-    pub fn peek(&mut self, player_index: u8, card_index: u8) -> Status {
-        ensure!(self.hand_card.is_some(), GameError::WrongPhase);
-        let card = self.hand_card.unwrap();
-        ensure!(card.0 == 7 || card.0 == 8, GameError::WrongCard);
+    // Sums up the values of each players face-down cards. The kabo caller gets
+    // the penalty from the scoring config, unless nobody else is as low as them.
+    pub fn score(&self) -> Vec<(String, u32)> {
+        self.players
+            .iter()
+            .zip(self.adjusted_scores())
+            .map(|(player, score)| (player.name.clone(), score))
+            .collect()
+    }
+    // Returns the index of the player with the lowest score, after the penalty
+    // for the kabo caller. Of several players with the same score, the one who
+    // comes first in the turn order wins.
+    pub fn winner(&self) -> Option<PlayerIndex> {
+        self.adjusted_scores()
+            .into_iter()
+            .enumerate()
+            .min_by_key(|&(_, score)| score)
+            .map(|(index, _)| PlayerIndex(index as u8))
+    }
+    fn adjusted_scores(&self) -> Vec<u32> {
+        let mut scores: Vec<u32> = self.players.iter().map(|player| player.score()).collect();
+        if let Some(kabo_index) = self.kabo {
+            let kabo_index = kabo_index.0 as usize;
+            let kabo_score = scores[kabo_index];
+            let strictly_lowest = scores.iter()
+                .enumerate()
+                .all(|(index, &score)| index == kabo_index || score > kabo_score);
+            if !strictly_lowest {
+                scores[kabo_index] += self.scoring.kabo_penalty;
+            }
+        }
 
-        unimplemented!()
+        scores
+    }
+    // Looks up a face-down card. The indices usually come from a client, so a card
+    // that doesn't exist is an error instead of a panic.
+    fn card_ref(&self,
+                player_index: PlayerIndex,
+                card_index: CardIndex)
+                -> Result<&FaceDownCard, GameError> {
+        self.players
+            .get(player_index.0 as usize)
+            .and_then(|player| player.cards.get(card_index.0 as usize))
+            .ok_or(GameError::InvalidIndex)
+    }
+    fn card_mut(&mut self,
+                player_index: PlayerIndex,
+                card_index: CardIndex)
+                -> Result<&mut FaceDownCard, GameError> {
+        self.players
+            .get_mut(player_index.0 as usize)
+            .and_then(|player| player.cards.get_mut(card_index.0 as usize))
+            .ok_or(GameError::InvalidIndex)
+    }
+    // Returns the card the current player is holding. If they don't hold one,
+    // the action is not allowed in this phase.
+    fn hand_card(&self) -> Result<Card, GameError> {
+        match self.phase {
+            TurnPhase::Holding { card, .. } => Ok(card),
+            TurnPhase::AwaitingDraw => Err(GameError::WrongPhase),
+            TurnPhase::Ended => Err(GameError::GameFinished),
+        }
+    }
+    // Who knows the hand card once it is put face down. Everyone saw the top card
+    // of the discard pile, but only the current player knows a card from the deck.
+    fn hand_card_known_by(&self, player_index: PlayerIndex) -> Vec<PlayerIndex> {
+        if matches!(self.phase, TurnPhase::Holding { from_discard: true, .. }) {
+            (0..self.players.len() as u8).map(PlayerIndex).collect()
+        } else {
+            vec![player_index]
+        }
+    }
+    // Like hand_card, but only for cards which may go straight onto the discard
+    // pile. Using a power discards the card as well. A player who snapped away
+    // all their cards has nothing to replace, so they may always discard.
+    fn discardable_hand_card(&self) -> Result<Card, GameError> {
+        let card = self.hand_card()?;
+        let nothing_to_replace = self.players[self.current_player.0 as usize].cards.is_empty();
+        ensure!(self.phase == TurnPhase::Holding { card, from_discard: false } ||
+                nothing_to_replace,
+                GameError::MustReplace);
+        Ok(card)
+    }
+    fn check_turn(&self, player_index: PlayerIndex) -> Pushed {
+        ensure!(player_index == self.current_player,
+                GameError::NotYourTurn {
+                    expected: self.current_player,
+                    got: player_index,
+                });
+        Ok(())
+    }
+    // The checks apply does before the action itself.
+    fn check_action(&self, player_index: PlayerIndex, action: &Action) -> Pushed {
+        ensure!(self.phase != TurnPhase::Ended, GameError::GameFinished);
+        // Snapping is the only action which doesn't have to wait for the own turn.
+        match *action {
+            Action::Snap { .. } => Ok(()),
+            _ => self.check_turn(player_index),
+        }
+    }
+    // Checks that the current player may call kabo instead of drawing.
+    fn check_kabo(&self) -> Pushed {
+        self.awaiting_draw()?;
+        if let Some(kabo_index) = self.kabo {
+            return Err(GameError::AlreadyKabo { player_index: kabo_index });
+        };
+        Ok(())
+    }
+    // Checks that the current player still has to draw a card.
+    fn awaiting_draw(&self) -> Result<(), GameError> {
+        match self.phase {
+            TurnPhase::AwaitingDraw => Ok(()),
+            TurnPhase::Holding { .. } => Err(GameError::WrongPhase),
+            TurnPhase::Ended => Err(GameError::GameFinished),
+        }
+    }
+    // The indices and card counts of all players except the current one.
+    fn other_card_counts(&self) -> Vec<(PlayerIndex, u8)> {
+        (0..self.players.len() as u8)
+            .map(PlayerIndex)
+            .filter(|&player_index| player_index != self.current_player)
+            .map(|player_index| {
+                (player_index, self.players[player_index.0 as usize].cards.len() as u8)
+            })
+            .collect()
     }
     // End turn can't be called manually. Any code that calls it has already
     // checked if the request is good so this can't return an error.
-    fn end_turn(&mut self) -> GameEvent {
+    // Passes the turn on and adds EndTurn and StartTurn for the next player to
+    // the events, or only GameOver once the final round is done.
+    fn end_turn(&mut self, events: &mut Vec<GameEvent>) {
         // End turn can't be called from the outside so this indicates a bug and panics.
-        assert!(self.hand_card.is_none(),
+        assert!(self.phase == TurnPhase::AwaitingDraw,
                 "Inconsistent state while ending Turn.");
 
-        self.current_player += 1;
-        if self.current_player as usize == self.players.len() {
-            self.current_player = 0;
+        // A single player left over has nobody to play against.
+        let mut game_over = self.out.iter().filter(|&&out| !out).count() < 2;
+        let mut next_player = self.current_player;
+        while !game_over {
+            next_player = PlayerIndex((next_player.0 + 1) % self.players.len() as u8);
+            // Play is back at the kabo caller, who doesn't get another turn.
+            // This also holds if the kabo caller is out by now.
+            if self.kabo == Some(next_player) {
+                game_over = true;
+            } else if !self.out[next_player.0 as usize] {
+                break;
+            }
         }
-        if let Some(kabo_index) = self.kabo {
-            if kabo_index == self.current_player {
-                return GameEvent::GameOver;
+        if game_over {
+            self.phase = TurnPhase::Ended;
+            events.push(GameEvent::GameOver);
+            return;
+        }
+
+        self.current_player = next_player;
+        events.push(GameEvent::EndTurn { next_player: self.current_player });
+        events.push(GameEvent::StartTurn { player_index: self.current_player });
+    }
+    // Takes the top card from the deck. If there is none, the discard pile is
+    // shuffled to become the new deck first. Only if both are empty, this fails.
+    fn draw_from_deck(&mut self, events: &mut Vec<GameEvent>) -> Result<Card, GameError> {
+        if self.deck.is_empty() {
+            ensure!(!self.discard_pile.is_empty(), GameError::OutOfCards);
+            swap(&mut self.deck, &mut self.discard_pile);
+            self.shuffler.shuffle(&mut self.deck);
+            // Yes, there is no remaining top card, but the player drawing is
+            // certain to discard a card soon.
+            events.push(GameEvent::DiscardShuffle);
+        }
+
+        Ok(self.deck.pop().unwrap())
+    }
+    // The actions themselves. They push their events into the given buffer, but
+    // only record_into adds them to the history.
+    fn deck_draw_into(&mut self, events: &mut Vec<GameEvent>) -> Pushed {
+        self.awaiting_draw()?;
+
+        let card = self.draw_from_deck(events)?;
+        self.phase = TurnPhase::Holding { card, from_discard: false };
+        events.push(GameEvent::CardDrawn {
+            recipient: self.current_player,
+            card,
+        });
+        Ok(())
+    }
+    fn discard_draw_into(&mut self, events: &mut Vec<GameEvent>) -> Pushed {
+        self.awaiting_draw()?;
+
+        // Right after the discard pile was shuffled into the deck, there is
+        // nothing to draw from it.
+        let card = self.discard_pile.pop().ok_or(GameError::EmptyDiscard)?;
+        self.phase = TurnPhase::Holding { card, from_discard: true };
+        events.push(GameEvent::CardDrawn {
+            recipient: self.current_player,
+            card,
+        });
+        Ok(())
+    }
+    fn announce_kabo_into(&mut self,
+                          player_index: PlayerIndex,
+                          events: &mut Vec<GameEvent>)
+                          -> Pushed {
+        self.check_kabo()?;
+        self.check_turn(player_index)?;
+
+        self.kabo = Some(player_index);
+        events.push(GameEvent::Kabo { player_index });
+        self.end_turn(events);
+        Ok(())
+    }
+    fn discard_into(&mut self, events: &mut Vec<GameEvent>) -> Pushed {
+        self.discardable_hand_card()?;
+
+        self.discard_and_end(events);
+        Ok(())
+    }
+    fn replace_into(&mut self,
+                    player_index: PlayerIndex,
+                    card_index: CardIndex,
+                    events: &mut Vec<GameEvent>)
+                    -> Pushed {
+        let mut card = self.hand_card()?;
+        self.check_turn(player_index)?;
+
+        let known_by = self.hand_card_known_by(player_index);
+        let face_down_card = self.card_mut(player_index, card_index)?;
+        swap(&mut card, &mut face_down_card.card);
+        face_down_card.known_by = known_by;
+        // The card which was face-down before is discarded now.
+        self.phase = TurnPhase::Holding { card, from_discard: false };
+
+        self.discard_and_end(events);
+        Ok(())
+    }
+    fn multi_replace_into(&mut self,
+                          player_index: PlayerIndex,
+                          card_indices: Vec<CardIndex>,
+                          events: &mut Vec<GameEvent>)
+                          -> Pushed {
+        let hand_card = self.hand_card()?;
+        ensure!(card_indices.len() >= 2, GameError::InvalidIndex);
+        self.check_turn(player_index)?;
+
+        // Validate all indices before anything is changed.
+        let mut cards_seen = Vec::with_capacity(card_indices.len());
+        for (i, &card_index) in card_indices.iter().enumerate() {
+            ensure!(!card_indices[..i].contains(&card_index),
+                    GameError::InvalidIndex);
+            cards_seen.push((card_index, self.card_ref(player_index, card_index)?.card));
+        }
+
+        if cards_seen.iter().all(|(_, card)| card.same_value(&hand_card)) {
+            let known_by = self.hand_card_known_by(player_index);
+            let replaced = self.card_mut(player_index, card_indices[0])?;
+            *replaced = FaceDownCard::new(hand_card);
+            replaced.known_by = known_by;
+            let cards = &mut self.players[player_index.0 as usize].cards;
+            // Remove the remaining cards back to front, so the indices stay valid.
+            let mut removed = card_indices[1..].to_vec();
+            removed.sort_unstable_by(|a, b| b.cmp(a));
+            for card_index in removed {
+                cards.remove(card_index.0 as usize);
+            }
+
+            let discards: Vec<Card> = cards_seen.iter().map(|&(_, card)| card).collect();
+            self.discard_pile.extend(&discards);
+            self.phase = TurnPhase::AwaitingDraw;
+
+            events.push(GameEvent::Discards { cards: discards });
+            self.end_turn(events);
+        } else {
+            // The claimed cards have been shown to everyone.
+            let player_count = self.players.len() as u8;
+            for &card_index in &card_indices {
+                let face_down_card = self.card_mut(player_index, card_index)?;
+                for other_player_index in 0..player_count {
+                    face_down_card.reveal_to(PlayerIndex(other_player_index));
+                }
             }
+            self.discard_pile.push(hand_card);
+            self.phase = TurnPhase::AwaitingDraw;
+
+            events.push(GameEvent::MultiReplaceFailure {
+                player_index,
+                card_type_claimed: hand_card,
+                cards_seen,
+            });
+            events.push(GameEvent::Discards { cards: vec![hand_card] });
+            // Nobody gets to see the penalty card.
+            let penalty_card = self.draw_from_deck(events)
+                .expect("The hand card was just discarded.");
+            self.players[player_index.0 as usize].cards.push(FaceDownCard::new(penalty_card));
+            self.end_turn(events);
         }
-        GameEvent::EndTurn { next_player: self.current_player }
+
+        Ok(())
+    }
+    fn peek_into(&mut self,
+                 player_index: PlayerIndex,
+                 card_index: CardIndex,
+                 events: &mut Vec<GameEvent>)
+                 -> Pushed {
+        let card = self.discardable_hand_card()?;
+        ensure!(card.power() == Some(Power::Peek), GameError::WrongCard);
+        self.check_turn(player_index)?;
+
+        let face_down_card = self.card_mut(player_index, card_index)?;
+        face_down_card.reveal_to(player_index);
+        events.push(GameEvent::Seen {
+            recipient: player_index,
+            player_index,
+            card_index,
+            card: face_down_card.card,
+        });
+
+        self.discard_and_end(events);
+        Ok(())
+    }
+    fn spy_into(&mut self,
+                other_player_index: PlayerIndex,
+                card_index: CardIndex,
+                events: &mut Vec<GameEvent>)
+                -> Pushed {
+        let card = self.discardable_hand_card()?;
+        ensure!(card.power() == Some(Power::Spy), GameError::WrongCard);
+        ensure!(other_player_index != self.current_player,
+                GameError::InvalidIndex);
+
+        let current_player = self.current_player;
+        let face_down_card = self.card_mut(other_player_index, card_index)?;
+        face_down_card.reveal_to(current_player);
+        events.push(GameEvent::Seen {
+            recipient: current_player,
+            player_index: other_player_index,
+            card_index,
+            card: face_down_card.card,
+        });
+
+        self.discard_and_end(events);
+        Ok(())
+    }
+    fn swap_into(&mut self,
+                 my_card_index: CardIndex,
+                 other_player_index: PlayerIndex,
+                 other_card_index: CardIndex,
+                 events: &mut Vec<GameEvent>)
+                 -> Pushed {
+        let card = self.discardable_hand_card()?;
+        ensure!(card.power() == Some(Power::Swap), GameError::WrongCard);
+        ensure!(other_player_index != self.current_player,
+                GameError::InvalidIndex);
+
+        // Whoever knew one of these cards still knows it after the swap.
+        let current_player = self.current_player;
+        let my_card = self.card_ref(current_player, my_card_index)?.clone();
+        let other_card = self.card_ref(other_player_index, other_card_index)?.clone();
+        *self.card_mut(current_player, my_card_index)? = other_card;
+        *self.card_mut(other_player_index, other_card_index)? = my_card;
+
+        events.push(GameEvent::Swapped {
+            player_index: self.current_player,
+            card_index: my_card_index,
+            other_player_index,
+            other_card_index,
+        });
+
+        self.discard_and_end(events);
+        Ok(())
+    }
+    fn snap_into(&mut self,
+                 player_index: PlayerIndex,
+                 card_index: CardIndex,
+                 events: &mut Vec<GameEvent>)
+                 -> Pushed {
+        ensure!(self.phase != TurnPhase::Ended, GameError::GameFinished);
+        let card = self.card_ref(player_index, card_index)?.card;
+        let discard_top = *self.discard_pile.last().ok_or(GameError::EmptyDiscard)?;
+
+        if card.same_value(&discard_top) {
+            self.players[player_index.0 as usize].cards.remove(card_index.0 as usize);
+            self.discard_pile.push(card);
+
+            events.push(GameEvent::Discards { cards: vec![card] });
+        } else {
+            let player_count = self.players.len() as u8;
+            let face_down_card = self.card_mut(player_index, card_index)?;
+            for other_player_index in 0..player_count {
+                face_down_card.reveal_to(PlayerIndex(other_player_index));
+            }
+
+            events.push(GameEvent::SnapFailure {
+                player_index,
+                card_index,
+                card,
+            });
+            // Nobody gets to see the penalty card.
+            let penalty_card = self.draw_from_deck(events)
+                .expect("The discard pile is not empty.");
+            self.players[player_index.0 as usize].cards.push(FaceDownCard::new(penalty_card));
+        }
+
+        Ok(())
+    }
+    fn undo_state(&self) -> UndoState {
+        UndoState {
+            deck: self.deck.clone(),
+            discard_pile: self.discard_pile.clone(),
+            players: self.players.clone(),
+            current_player: self.current_player,
+            kabo: self.kabo,
+            phase: self.phase,
+            shuffler: self.shuffler.clone(),
+            history_len: self.history.len(),
+        }
+    }
+    // Like undo_state, but reuses the memory of an old state.
+    fn save_undo_state(&self, state: &mut UndoState) {
+        state.deck.clone_from(&self.deck);
+        state.discard_pile.clone_from(&self.discard_pile);
+        state.players.clone_from(&self.players);
+        state.current_player = self.current_player;
+        state.kabo = self.kabo;
+        state.phase = self.phase;
+        state.shuffler.clone_from(&self.shuffler);
+        state.history_len = self.history.len();
+    }
+    // Runs an action with a fresh buffer, for the methods which return Status.
+    fn collect<F>(&mut self, action: F) -> Status
+        where F: FnOnce(&mut Game, &mut Vec<GameEvent>) -> Pushed
+    {
+        let mut events = vec![];
+        self.record_into(&mut events, action)?;
+        Ok(events)
+    }
+    // Runs an action and appends its public events to the history. If the action
+    // fails, anything it pushed is removed again. Otherwise the state from before
+    // is kept for undo.
+    // Every successful move ends here, so this is where the invariants are checked.
+    fn record_into<F>(&mut self, events: &mut Vec<GameEvent>, action: F) -> Pushed
+        where F: FnOnce(&mut Game, &mut Vec<GameEvent>) -> Pushed
+    {
+        let before = match self.spare_state.take() {
+            Some(mut state) => {
+                self.save_undo_state(&mut state);
+                state
+            }
+            None => self.undo_state(),
+        };
+        let start = events.len();
+        if let Err(error) = action(self, events) {
+            // Failed actions don't change anything, so there is nothing to undo.
+            self.spare_state = Some(before);
+            events.truncate(start);
+            self.observer.error(&error);
+            return Err(error);
+        }
+        if self.undo_stack.len() == UNDO_DEPTH {
+            self.spare_state = Some(self.undo_stack.remove(0));
+        }
+        self.undo_stack.push(before);
+        self.check_invariants();
+
+        for event in &events[start..] {
+            self.observer.event(event);
+            if event.visibility() == Visibility::Public {
+                self.history.push(event.clone());
+            }
+        }
+
+        Ok(())
     }
     // Panics, if there is no hand card.
-    fn discard_and_end(&mut self) -> Vec<GameEvent> {
-        let card = self.hand_card.unwrap();
+    // All actions which use the hand card end with this, so observers always see
+    // the card hit the discard pile before the turn ends.
+    fn discard_and_end(&mut self, events: &mut Vec<GameEvent>) {
+        let card = self.hand_card().unwrap();
         self.discard_pile.push(card);
-        self.hand_card = None;
+        self.phase = TurnPhase::AwaitingDraw;
 
-        vec![GameEvent::Discards { cards: vec![card] }, self.end_turn()]
+        events.push(GameEvent::Discards { cards: vec![card] });
+        self.end_turn(events);
     }
 }
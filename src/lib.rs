@@ -0,0 +1,16 @@
+// Kabo implements the game mechanics for the card game Cabo and the messages
+// to talk about it.
+
+extern crate rand;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(feature = "serde")]
+extern crate serde_json;
+
+pub mod ai;
+pub mod game;
+pub mod matches;
+pub mod messages;
+#[cfg(feature = "serde")]
+pub mod server;
+pub mod transport;
@@ -1,21 +1,28 @@
 // Thinking about the same thing with types.
 
-extern crate rand;
-mod game;
+extern crate kabo;
+use kabo::game;
+use kabo::game::{CardIndex, PlayerIndex};
 
 fn main() {
     println!("Hello, world!");
 
-    let mut pre_game = game::PreGame::new(vec!["Judita", "Sara", "Rolf"], 4);
+    let mut pre_game = game::PreGame::builder()
+        .player("Judita")
+        .player("Sara")
+        .player("Rolf")
+        .cards_per_player(4)
+        .build()
+        .unwrap();
 
     println!("The game is: {:?}.", pre_game);
 
-    pre_game.peek(0, 0).unwrap();
-    pre_game.peek(0, 1).unwrap();
-    pre_game.peek(1, 0).unwrap();
-    pre_game.peek(1, 1).unwrap();
-    pre_game.peek(2, 0).unwrap();
-    pre_game.peek(2, 1).unwrap();
+    pre_game.peek(PlayerIndex(0), CardIndex(0)).unwrap();
+    pre_game.peek(PlayerIndex(0), CardIndex(1)).unwrap();
+    pre_game.peek(PlayerIndex(1), CardIndex(0)).unwrap();
+    pre_game.peek(PlayerIndex(1), CardIndex(1)).unwrap();
+    pre_game.peek(PlayerIndex(2), CardIndex(0)).unwrap();
+    pre_game.peek(PlayerIndex(2), CardIndex(1)).unwrap();
 
     let mut game = pre_game.to_game();
     game.deck_draw().unwrap();
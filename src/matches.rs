@@ -0,0 +1,75 @@
+// A match consists of several rounds of Kabo. The scores of each round are
+// added up until a player crosses the threshold.
+
+use game::{Game, PlayerIndex, PreGame, PreGameBuilder, PreGameError};
+
+const DEFAULT_THRESHOLD: u32 = 100;
+
+#[derive(Debug, Clone)]
+pub struct Match {
+    // Every round is dealt with these settings.
+    builder: PreGameBuilder,
+    scores: Vec<(String, u32)>,
+    threshold: u32,
+    rounds_played: usize,
+}
+
+impl Match {
+    pub fn new(names: Vec<&str>, cards_per_player: u8) -> Self {
+        Match::with_threshold(names, cards_per_player, DEFAULT_THRESHOLD)
+    }
+    // The match is over once a player has more points than the threshold.
+    pub fn with_threshold(names: Vec<&str>, cards_per_player: u8, threshold: u32) -> Self {
+        let builder = names.into_iter()
+            .fold(PreGame::builder(), |builder, name| builder.player(name))
+            .cards_per_player(cards_per_player);
+        Match::from_builder(builder, threshold)
+    }
+    // Plays every round with the players and settings of the builder. With a
+    // seed, each round still gets another deal.
+    pub fn from_builder(builder: PreGameBuilder, threshold: u32) -> Self {
+        Match {
+            scores: builder.names().iter().map(|name| (name.clone(), 0)).collect(),
+            builder,
+            threshold,
+            rounds_played: 0,
+        }
+    }
+    // Deals a new round for the same players.
+    pub fn next_round(&self) -> Result<PreGame, PreGameError> {
+        self.builder.clone().for_round(self.rounds_played as u64).build()
+    }
+    // Starts a round dealt by next_round once every peek is used, with the
+    // first player of this round.
+    pub fn start_round(&self, pre_game: PreGame) -> Game {
+        let mut game = pre_game.to_game();
+        game.set_first_player(self.first_player())
+            .expect("The round was dealt for different players.");
+        game
+    }
+    // Adds the scores of a finished round to the totals and returns whether
+    // the match is over.
+    pub fn finish_round(&mut self, game: Game) -> bool {
+        let round_scores = game.score();
+        assert!(round_scores.len() == self.scores.len(),
+                "The round was played by different players.");
+
+        for (total, (name, score)) in self.scores.iter_mut().zip(round_scores) {
+            assert!(total.0 == name, "The round was played by different players.");
+            total.1 += score;
+        }
+        self.rounds_played += 1;
+
+        self.is_over()
+    }
+    // The first player moves on by one every round, start_round applies it.
+    pub fn first_player(&self) -> PlayerIndex {
+        PlayerIndex((self.rounds_played % self.scores.len()) as u8)
+    }
+    pub fn is_over(&self) -> bool {
+        self.scores.iter().any(|&(_, score)| score > self.threshold)
+    }
+    pub fn scores(&self) -> &[(String, u32)] {
+        &self.scores
+    }
+}
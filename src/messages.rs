@@ -1,56 +1,127 @@
-use game::{GameEvent, Card};
+use game::{ActionResult, Card, CardIndex, Game, GameError, GameEvent, PlayerIndex, PlayerView,
+           Status, Visibility};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Action {
     // Before you hold a card
     DeckDraw,
     DiscardDraw,
     Kabo,
     // When you hold a card
-    Replace { card_index: u8 },
+    Replace { card_index: CardIndex },
     MultiReplace {
         card_type: Card,
-        card_indices: Vec<u8>,
+        card_indices: Vec<CardIndex>,
     },
-    Peek { card_index: u8 },
+    Peek { card_index: CardIndex },
     Spy {
-        other_player_index: u8,
-        card_index: u8,
+        other_player_index: PlayerIndex,
+        card_index: CardIndex,
     },
     Swap {
-        my_card_index: u8,
-        other_player_index: u8,
-        other_card_index: u8,
+        my_card_index: CardIndex,
+        other_player_index: PlayerIndex,
+        other_card_index: CardIndex,
     },
     Discard,
+    // At any time, even during the turn of another player
+    Snap { card_index: CardIndex },
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Player {
     // At any time:
-    AskState { player_index: u8 },
+    AskState { player_index: PlayerIndex },
     Play { action: Action },
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Server {
     // Public information
-    StartTurn { player_index: u8 },
+    StartTurn { player_index: PlayerIndex },
     ActionSuccess {
-        player_index: u8,
+        player_index: PlayerIndex,
         action: Action,
         discards: Vec<Card>,
     },
     MultiReplaceFailure {
-        player_index: u8,
+        player_index: PlayerIndex,
         card_type_claimed: Card,
-        cards_seen: Vec<(u8, Card)>,
+        cards_seen: Vec<(CardIndex, Card)>,
+    },
+    SnapFailure {
+        player_index: PlayerIndex,
+        card_index: CardIndex,
+        card: Card,
     },
     // Private information
     CardDrawn { card: Card },
     CardSeen {
-        player_index: u8,
-        card_index: u8,
+        player_index: PlayerIndex,
+        card_index: CardIndex,
         card: Card,
     },
+    State { view: PlayerView },
     Error { error: GameError },
+    // Public information
+    GameOver { scores: Vec<(String, u32)> },
+}
+
+// Translates the outcome of an action by player_index into the messages for the
+// players. A success is announced to everyone, followed by the messages for the
+// events. Every message keeps the visibility of its event, so private cards only
+// reach their recipient. An error is only sent to the player who acted.
+// Events which nobody needs to be told about separately are left out. The game is
+// needed for the final scores.
+pub fn to_server_messages(game: &Game,
+                          player_index: PlayerIndex,
+                          action: &Action,
+                          result: &Status)
+                          -> Vec<(Visibility, Server)> {
+    let events = match *result {
+        Ok(ref events) => events,
+        Err(ref error) => {
+            let recipient = Visibility::Private { recipient: player_index };
+            return vec![(recipient, Server::Error { error: error.clone() })];
+        }
+    };
+
+    let success = ActionResult::new(player_index, action.clone(), events.clone());
+    let mut messages = vec![(Visibility::Public, success.success_message())];
+    for event in events {
+        let message = match *event {
+            GameEvent::CardDrawn { card, .. } => Server::CardDrawn { card },
+            GameEvent::Seen {
+                player_index,
+                card_index,
+                card,
+                ..
+            } => Server::CardSeen { player_index, card_index, card },
+            GameEvent::MultiReplaceFailure {
+                player_index,
+                card_type_claimed,
+                ref cards_seen,
+            } => {
+                Server::MultiReplaceFailure {
+                    player_index,
+                    card_type_claimed,
+                    cards_seen: cards_seen.clone(),
+                }
+            }
+            GameEvent::SnapFailure { player_index, card_index, card } => {
+                Server::SnapFailure { player_index, card_index, card }
+            }
+            GameEvent::StartTurn { player_index } => Server::StartTurn { player_index },
+            GameEvent::GameOver => Server::GameOver { scores: game.score() },
+            _ => continue,
+        };
+        messages.push((event.visibility(), message));
+    }
+
+    messages
 }
@@ -0,0 +1,76 @@
+// A server which plays a single game over TCP. Each of the first connections is
+// one player, in the order they connect. Clients send one messages::Player as
+// JSON per line and receive messages::Server as JSON lines.
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread;
+
+use serde_json;
+
+use game::{Game, GameError, PlayerIndex};
+use messages::{Player, Server};
+use transport::{play, Transport};
+
+// The connections of all players. Every connection has a thread which reads its lines.
+struct TcpTransport {
+    connections: Vec<TcpStream>,
+    receiver: Receiver<(PlayerIndex, String)>,
+}
+
+// Waits for all players to connect and then plays the game until it is over
+// or everyone has disconnected.
+pub fn serve(listener: TcpListener, game: Game) -> io::Result<()> {
+    let (sender, receiver) = channel();
+    let mut connections = Vec::with_capacity(game.players().len());
+    for player_index in 0..game.players().len() as u8 {
+        let (stream, _) = listener.accept()?;
+        let reader = stream.try_clone()?;
+        let sender = sender.clone();
+        thread::spawn(move || read_lines(PlayerIndex(player_index), reader, sender));
+        connections.push(stream);
+    }
+    // Only the reading threads may keep the channel open, so the game ends
+    // once every player has disconnected.
+    drop(sender);
+
+    play(&mut TcpTransport { connections, receiver }, game)
+}
+
+impl Transport for TcpTransport {
+    fn send(&mut self, player_index: PlayerIndex, message: &Server) -> io::Result<()> {
+        let stream = &mut self.connections[player_index.0 as usize];
+        serde_json::to_writer(&mut *stream, message)?;
+        stream.write_all(b"\n")
+    }
+    // A line which is no valid message is answered right here. If that answer
+    // can't be sent, the connection is broken and the next message to everyone
+    // fails as well.
+    fn recv(&mut self) -> Option<(PlayerIndex, Player)> {
+        loop {
+            let (player_index, line) = self.receiver.recv().ok()?;
+            match serde_json::from_str(&line) {
+                Ok(message) => return Some((player_index, message)),
+                Err(_) => {
+                    let error = Server::Error { error: GameError::MalformedMessage };
+                    let _ = self.send(player_index, &error);
+                }
+            }
+        }
+    }
+}
+
+// Forwards every line a player sends, until the connection is closed.
+fn read_lines(player_index: PlayerIndex, stream: TcpStream, sender: Sender<(PlayerIndex, String)>) {
+    for line in BufReader::new(stream).lines() {
+        match line {
+            Ok(line) => {
+                if sender.send((player_index, line)).is_err() {
+                    return;
+                }
+            }
+            Err(_) => return,
+        }
+    }
+}
@@ -0,0 +1,130 @@
+// Plays a game with players who are connected in some way, for example over TCP
+// with the server module. The game loop only talks to the Transport trait, the
+// channel based transport below needs no sockets at all.
+
+use std::io;
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+use game::{Game, GameError, PlayerIndex, Visibility};
+use messages::{to_server_messages, Player, Server};
+
+// All connections of a single game.
+pub trait Transport {
+    // Sends a message to a single player.
+    fn send(&mut self, player_index: PlayerIndex, message: &Server) -> io::Result<()>;
+    // Waits for the next message from any player. Returns None once nobody can
+    // send anything anymore.
+    fn recv(&mut self) -> Option<(PlayerIndex, Player)>;
+}
+
+// Plays the game until it is over or all players are gone.
+pub fn play<T: Transport>(transport: &mut T, mut game: Game) -> io::Result<()> {
+    let player_count = game.players().len() as u8;
+    let start = Server::StartTurn { player_index: game.current_player() };
+    for player_index in 0..player_count {
+        transport.send(PlayerIndex(player_index), &start)?;
+    }
+
+    while let Some((player_index, message)) = transport.recv() {
+        let mut game_over = false;
+        // Private information must only be sent to a single player.
+        for (visibility, message) in handle(&mut game, player_index, message) {
+            if let Server::GameOver { .. } = message {
+                game_over = true;
+            }
+            match visibility {
+                Visibility::Public => {
+                    for player_index in 0..player_count {
+                        transport.send(PlayerIndex(player_index), &message)?;
+                    }
+                }
+                Visibility::Private { recipient } => transport.send(recipient, &message)?,
+            }
+        }
+        if game_over {
+            return Ok(());
+        }
+    }
+
+    Ok(())
+}
+
+// Executes a message from a player and returns the answers for everyone.
+fn handle(game: &mut Game, sender: PlayerIndex, message: Player) -> Vec<(Visibility, Server)> {
+    let action = match message {
+        Player::AskState { player_index } => {
+            // Nobody may look at the state of another player.
+            let answer = if player_index == sender {
+                Server::State { view: game.view_for(sender) }
+            } else {
+                Server::Error { error: GameError::InvalidIndex }
+            };
+            return vec![(Visibility::Private { recipient: sender }, answer)];
+        }
+        Player::Play { action } => action,
+    };
+
+    let result = game.apply(sender, action.clone()).map(|result| result.events);
+    to_server_messages(game, sender, &action, &result)
+}
+
+// Connects the game loop to clients in the same process, see channel_transport.
+pub struct ChannelTransport {
+    receiver: Receiver<(PlayerIndex, Player)>,
+    senders: Vec<Sender<Server>>,
+}
+
+// The other end of a ChannelTransport, for a single player.
+pub struct ChannelClient {
+    player_index: PlayerIndex,
+    sender: Sender<(PlayerIndex, Player)>,
+    receiver: Receiver<Server>,
+}
+
+// Creates a transport with one client for each of the players.
+pub fn channel_transport(player_count: u8) -> (ChannelTransport, Vec<ChannelClient>) {
+    let (sender, receiver) = channel();
+    let mut senders = vec![];
+    let mut clients = vec![];
+    for player_index in 0..player_count {
+        let (server_sender, client_receiver) = channel();
+        senders.push(server_sender);
+        clients.push(ChannelClient {
+            player_index: PlayerIndex(player_index),
+            sender: sender.clone(),
+            receiver: client_receiver,
+        });
+    }
+
+    (ChannelTransport { receiver, senders }, clients)
+}
+
+impl Transport for ChannelTransport {
+    // A client which was dropped just doesn't get the message.
+    fn send(&mut self, player_index: PlayerIndex, message: &Server) -> io::Result<()> {
+        let sender = self.senders
+            .get(player_index.0 as usize)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Invalid player index."))?;
+        let _ = sender.send(message.clone());
+        Ok(())
+    }
+    // Ends once all clients were dropped.
+    fn recv(&mut self) -> Option<(PlayerIndex, Player)> {
+        self.receiver.recv().ok()
+    }
+}
+
+impl ChannelClient {
+    pub fn player_index(&self) -> PlayerIndex {
+        self.player_index
+    }
+    // Returns false if the game loop is already over.
+    pub fn send(&self, message: Player) -> bool {
+        self.sender.send((self.player_index, message)).is_ok()
+    }
+    // Waits for the next message. Returns None once the game loop is over and
+    // every message was received.
+    pub fn recv(&self) -> Option<Server> {
+        self.receiver.recv().ok()
+    }
+}
@@ -0,0 +1,384 @@
+// Checks single actions on a seeded game.
+
+extern crate kabo;
+
+mod common;
+
+use common::{builder, two_player_game};
+use kabo::game::{Card, CardIndex, GameError, GameEvent, Observer, PlayerIndex, Power, PreGameError,
+                 Visibility};
+use kabo::messages::Action;
+use std::error::Error;
+use std::sync::{Arc, Mutex};
+
+#[test]
+fn discard_top_is_the_discarded_card() {
+    let mut game = two_player_game(7);
+    let deck_size = game.deck_size();
+
+    game.apply(PlayerIndex(0), Action::DeckDraw).unwrap();
+    let drawn = game.view_for(PlayerIndex(0)).hand_card.unwrap();
+    assert_eq!(game.deck_size(), deck_size - 1);
+
+    game.apply(PlayerIndex(0), Action::Discard).unwrap();
+    assert_eq!(game.discard_top(), Some(drawn));
+    assert_eq!(game.view_for(PlayerIndex(1)).discard_top, Some(drawn));
+}
+
+#[test]
+fn acting_out_of_turn_names_both_players() {
+    let mut game = two_player_game(7);
+
+    let error = game.apply(PlayerIndex(1), Action::DeckDraw).unwrap_err();
+    assert_eq!(error,
+               GameError::NotYourTurn {
+                   expected: PlayerIndex(0),
+                   got: PlayerIndex(1),
+               });
+    assert!(!game.is_holding_card());
+}
+
+#[test]
+fn the_drawn_card_is_only_shown_to_the_current_player() {
+    let mut game = two_player_game(7);
+
+    let events = game.apply(PlayerIndex(0), Action::DeckDraw).unwrap().events;
+    let hand_card = game.view_for(PlayerIndex(0)).hand_card.unwrap();
+    assert_eq!(events,
+               vec![GameEvent::CardDrawn {
+                        recipient: PlayerIndex(0),
+                        card: hand_card,
+                    }]);
+    assert_eq!(events[0].visibility(),
+               Visibility::Private { recipient: PlayerIndex(0) });
+    assert!(!game.history().contains(&events[0]));
+}
+
+#[test]
+fn the_first_player_can_only_be_changed_before_the_first_move() {
+    let mut game = two_player_game(7);
+
+    game.set_first_player(PlayerIndex(1)).unwrap();
+    assert_eq!(game.history(),
+               &[GameEvent::StartTurn { player_index: PlayerIndex(1) }]);
+    game.apply(PlayerIndex(1), Action::DeckDraw).unwrap();
+
+    assert_eq!(game.set_first_player(PlayerIndex(0)), Err(GameError::WrongPhase));
+    assert_eq!(game.current_player(), PlayerIndex(1));
+}
+
+#[test]
+fn cards_can_be_parsed() {
+    for number in 0..13 {
+        let card: Card = number.to_string().parse().unwrap();
+        assert_eq!(card.value(), number);
+        assert_eq!(card.to_string(), number.to_string());
+    }
+
+    let red_king: Card = "K".parse().unwrap();
+    assert!(red_king.is_red_king());
+    assert_eq!(red_king.score(), 0);
+    let black_king: Card = "13".parse().unwrap();
+    assert!(black_king.same_value(&red_king));
+    assert_eq!(black_king.score(), 13);
+
+    assert_eq!(Card::try_new(13).map(|card| card.value()), Ok(13));
+    assert_eq!(Card::try_new(14), Err(GameError::InvalidCard));
+    assert_eq!("14".parse::<Card>(), Err(GameError::InvalidCard));
+    assert_eq!("seven".parse::<Card>(), Err(GameError::InvalidCard));
+}
+
+#[test]
+fn only_the_seven_to_twelve_have_a_power() {
+    for number in 0..14 {
+        let card = Card::try_new(number).unwrap();
+        let power = match number {
+            7 | 8 => Some(Power::Peek),
+            9 | 10 => Some(Power::Spy),
+            11 | 12 => Some(Power::Swap),
+            _ => None,
+        };
+        assert_eq!(card.power(), power, "card {}", number);
+        assert_eq!(card.is_special(), power.is_some(), "card {}", number);
+    }
+    assert_eq!(Card::red_king().power(), None);
+}
+
+#[test]
+fn the_pre_game_is_ready_once_every_peek_is_used() {
+    let mut pre_game = builder(&["Judita", "Sara"], 7).build().unwrap();
+    assert_eq!(pre_game.total_peeks_left(), 4);
+
+    for player_index in 0..2 {
+        for card_index in 0..2 {
+            assert!(!pre_game.ready());
+            pre_game.peek(PlayerIndex(player_index), CardIndex(card_index)).unwrap();
+        }
+        assert_eq!(pre_game.peeks_left(PlayerIndex(player_index)), 0);
+    }
+
+    assert_eq!(pre_game.total_peeks_left(), 0);
+    assert!(pre_game.ready());
+}
+
+#[test]
+fn a_single_peek_is_used_up_after_one_card() {
+    let mut pre_game = builder(&["Judita", "Sara"], 7).peeks(1).build().unwrap();
+    assert_eq!(pre_game.peeks_left(PlayerIndex(0)), 1);
+
+    pre_game.peek(PlayerIndex(0), CardIndex(0)).unwrap();
+    assert_eq!(pre_game.peek(PlayerIndex(0), CardIndex(1)),
+               Err(PreGameError::NoPeeksLeft));
+    assert_eq!(pre_game.peeks_left(PlayerIndex(0)), 0);
+    // Sara still has her own peek.
+    assert_eq!(pre_game.total_peeks_left(), 1);
+}
+
+#[test]
+fn peeking_at_a_card_twice_keeps_the_peek() {
+    let mut pre_game = builder(&["Judita", "Sara"], 7).build().unwrap();
+    pre_game.peek(PlayerIndex(0), CardIndex(1)).unwrap();
+
+    assert_eq!(pre_game.peek(PlayerIndex(0), CardIndex(1)),
+               Err(PreGameError::AlreadyPeeked));
+    assert_eq!(pre_game.peeks_left(PlayerIndex(0)), 1);
+    assert_eq!(pre_game.total_peeks_left(), 3);
+
+    // The remaining peek still works on another card.
+    pre_game.peek(PlayerIndex(0), CardIndex(2)).unwrap();
+    assert_eq!(pre_game.peeks_left(PlayerIndex(0)), 0);
+    assert_eq!(pre_game.total_peeks_left(), 2);
+}
+
+#[test]
+fn a_new_deal_has_the_standard_deck() {
+    let pre_game = builder(&["Judita", "Sara"], 7).build().unwrap();
+    assert!(pre_game.verify_deck_composition());
+}
+
+// Shares what it records with the test, which can't get the box back as a Recorder.
+struct Recorder {
+    events: Arc<Mutex<Vec<GameEvent>>>,
+    errors: Arc<Mutex<Vec<GameError>>>,
+}
+
+impl Observer for Recorder {
+    fn on_event(&mut self, event: &GameEvent) {
+        self.events.lock().unwrap().push(event.clone());
+    }
+    fn on_error(&mut self, error: &GameError) {
+        self.errors.lock().unwrap().push(error.clone());
+    }
+}
+
+#[test]
+fn the_observer_sees_the_events_of_a_discard() {
+    let mut game = two_player_game(7);
+    let events = Arc::new(Mutex::new(vec![]));
+    let errors = Arc::new(Mutex::new(vec![]));
+    game.set_observer(Box::new(Recorder {
+        events: events.clone(),
+        errors: errors.clone(),
+    }));
+
+    game.deck_draw().unwrap();
+    events.lock().unwrap().clear();
+    let discarded = game.discard().unwrap();
+    assert_eq!(*events.lock().unwrap(), discarded);
+
+    let error = game.apply(PlayerIndex(0), Action::DiscardDraw).unwrap_err();
+    assert_eq!(*errors.lock().unwrap(), vec![error]);
+}
+
+#[test]
+fn only_the_current_player_may_call_kabo() {
+    let mut game = two_player_game(7);
+
+    // Even without apply, Sara can't call kabo during the turn of Judita.
+    assert_eq!(game.announce_kabo(PlayerIndex(1)),
+               Err(GameError::NotYourTurn {
+                   expected: PlayerIndex(0),
+                   got: PlayerIndex(1),
+               }));
+    assert_eq!(game.kabo_caller(), None);
+    assert_eq!(game.history().len(), 1);
+
+    game.announce_kabo(PlayerIndex(0)).unwrap();
+    assert_eq!(game.kabo_caller(), Some(PlayerIndex(0)));
+}
+
+#[test]
+fn kabo_can_only_be_called_before_drawing_and_only_once() {
+    let mut game = two_player_game(7);
+    assert!(game.can_call_kabo());
+    assert!(!game.in_final_round());
+
+    game.apply(PlayerIndex(0), Action::DeckDraw).unwrap();
+    assert!(!game.can_call_kabo());
+    assert_eq!(game.apply(PlayerIndex(0), Action::Kabo).unwrap_err(),
+               GameError::WrongPhase);
+    game.apply(PlayerIndex(0), Action::Discard).unwrap();
+
+    game.apply(PlayerIndex(1), Action::Kabo).unwrap();
+    assert!(game.in_final_round());
+    assert!(!game.can_call_kabo());
+    assert_eq!(game.apply(PlayerIndex(0), Action::Kabo).unwrap_err(),
+               GameError::AlreadyKabo { player_index: PlayerIndex(1) });
+
+    // Judita's last turn ends the game.
+    game.apply(PlayerIndex(0), Action::DeckDraw).unwrap();
+    game.apply(PlayerIndex(0), Action::Discard).unwrap();
+    assert!(!game.in_final_round());
+}
+
+#[test]
+fn undoing_a_draw_and_a_replace_restores_the_start() {
+    let mut game = two_player_game(7);
+    let original = game.clone();
+
+    game.apply(PlayerIndex(0), Action::DeckDraw).unwrap();
+    let drawn = game.view_for(PlayerIndex(0)).hand_card;
+    game.apply(PlayerIndex(0), Action::Replace { card_index: CardIndex(3) }).unwrap();
+    // A failed move is not undone.
+    game.apply(PlayerIndex(1), Action::Discard).unwrap_err();
+    game.undo().unwrap();
+    game.undo().unwrap();
+    assert_eq!(game.undo(), Err(GameError::NothingToUndo));
+
+    assert_eq!(game.snapshot(), original.snapshot());
+    assert_eq!(game.history(), original.history());
+    for player_index in 0..2 {
+        assert_eq!(game.view_for(PlayerIndex(player_index)),
+                   original.view_for(PlayerIndex(player_index)));
+    }
+    // The deck is back in its old order.
+    game.apply(PlayerIndex(0), Action::DeckDraw).unwrap();
+    assert_eq!(game.view_for(PlayerIndex(0)).hand_card, drawn);
+}
+
+#[test]
+fn a_card_from_the_discard_pile_must_replace_a_card() {
+    let mut game = two_player_game(7);
+
+    game.apply(PlayerIndex(0), Action::DiscardDraw).unwrap();
+    assert_eq!(game.apply(PlayerIndex(0), Action::Discard).unwrap_err(),
+               GameError::MustReplace);
+    assert!(game.is_holding_card());
+    game.apply(PlayerIndex(0), Action::Replace { card_index: CardIndex(0) }).unwrap();
+
+    // A card from the deck may be discarded right away.
+    game.apply(PlayerIndex(1), Action::DeckDraw).unwrap();
+    game.apply(PlayerIndex(1), Action::Discard).unwrap();
+    assert_eq!(game.current_player(), PlayerIndex(0));
+}
+
+#[test]
+fn without_a_card_only_drawing_and_kabo_are_legal() {
+    let mut game = two_player_game(7);
+    assert_eq!(game.legal_actions(),
+               vec![Action::DeckDraw, Action::DiscardDraw, Action::Kabo]);
+
+    game.apply(PlayerIndex(0), Action::Kabo).unwrap();
+    assert_eq!(game.legal_actions(), vec![Action::DeckDraw, Action::DiscardDraw]);
+}
+
+#[test]
+fn errors_explain_themselves() {
+    assert_eq!(GameError::NotYourTurn {
+                       expected: PlayerIndex(0),
+                       got: PlayerIndex(1),
+                   }
+                   .to_string(),
+               "it is the turn of player 0, not of player 1");
+    assert_eq!(GameError::AlreadyKabo { player_index: PlayerIndex(2) }.to_string(),
+               "player 2 has already called kabo");
+    assert_eq!(GameError::EmptyDiscard.to_string(), "the discard pile is empty");
+    assert_eq!(PreGameError::NotEnoughPlayers.to_string(),
+               "at least two players are needed");
+    assert_eq!(PreGameError::AlreadyPeeked.to_string(),
+               "the player already knows this card");
+
+    // They can be passed on like any other error.
+    let error: Box<dyn Error> = Box::new(GameError::OutOfCards);
+    assert_eq!(error.to_string(), "there are no cards left to draw");
+}
+
+#[test]
+fn nobody_replaces_the_cards_of_another_player() {
+    let mut game = two_player_game(7);
+    game.apply(PlayerIndex(0), Action::DeckDraw).unwrap();
+    let before = game.clone();
+
+    let not_your_turn = GameError::NotYourTurn {
+        expected: PlayerIndex(0),
+        got: PlayerIndex(1),
+    };
+    assert_eq!(game.replace(PlayerIndex(1), CardIndex(0)), Err(not_your_turn.clone()));
+    assert_eq!(game.multi_replace(PlayerIndex(1), vec![CardIndex(0), CardIndex(1)]),
+               Err(not_your_turn));
+
+    assert_eq!(game.snapshot(), before.snapshot());
+    assert_eq!(game.history(), before.history());
+    for player_index in 0..2 {
+        assert_eq!(game.view_for(PlayerIndex(player_index)),
+                   before.view_for(PlayerIndex(player_index)));
+    }
+}
+
+#[test]
+fn only_peeked_cards_are_known() {
+    let mut pre_game = builder(&["Judita", "Sara"], 7).build().unwrap();
+    let first = pre_game.peek(PlayerIndex(0), CardIndex(0)).unwrap();
+    let third = pre_game.peek(PlayerIndex(0), CardIndex(2)).unwrap();
+    for card_index in 0..2 {
+        pre_game.peek(PlayerIndex(1), CardIndex(card_index)).unwrap();
+    }
+    let game = pre_game.to_game();
+    let history = game.history().to_vec();
+
+    assert_eq!(game.known_card(PlayerIndex(0), CardIndex(0)), Some(first));
+    assert_eq!(game.known_card(PlayerIndex(0), CardIndex(1)), None);
+    assert_eq!(game.known_card(PlayerIndex(0), CardIndex(2)), Some(third));
+    assert_eq!(game.known_card(PlayerIndex(0), CardIndex(4)), None);
+    // Looking doesn't count as a move.
+    assert_eq!(game.history(), &history[..]);
+    assert_eq!(game.current_player(), PlayerIndex(0));
+}
+
+#[test]
+fn a_forced_discard_ends_the_turn() {
+    let mut game = two_player_game(7);
+    assert_eq!(game.force_discard(), Err(GameError::WrongPhase));
+
+    game.deck_draw().unwrap();
+    let drawn = game.view_for(PlayerIndex(0)).hand_card.unwrap();
+    let events = game.force_discard().unwrap();
+    assert_eq!(events,
+               vec![GameEvent::Discards { cards: vec![drawn] },
+                    GameEvent::EndTurn { next_player: PlayerIndex(1) },
+                    GameEvent::StartTurn { player_index: PlayerIndex(1) }]);
+    assert_eq!(game.discard_top(), Some(drawn));
+    assert_eq!(game.current_player(), PlayerIndex(1));
+    assert!(!game.is_holding_card());
+}
+
+#[test]
+fn a_seed_always_deals_the_same_draws() {
+    let mut game = two_player_game(7);
+    let mut drawn = vec![];
+    for _ in 0..6 {
+        for event in game.deck_draw().unwrap() {
+            if let GameEvent::CardDrawn { card, .. } = event {
+                drawn.push(card);
+            }
+        }
+        game.discard().unwrap();
+    }
+
+    // These only change if dealing or shuffling changes, so every seeded game would.
+    let expected: Vec<Card> = ["7", "1", "10", "4", "9", "7"]
+        .iter()
+        .map(|card| card.parse().unwrap())
+        .collect();
+    assert_eq!(drawn, expected);
+}
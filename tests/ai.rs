@@ -0,0 +1,19 @@
+// Lets the computer players play against each other.
+
+extern crate kabo;
+
+mod common;
+
+use common::{play_to_the_end, start_game};
+use kabo::ai::choose_action;
+
+#[test]
+fn two_computer_players_finish_the_game() {
+    for seed in 0..50 {
+        let mut game = start_game(&["Judita", "Sara"], seed);
+        play_to_the_end(&mut game);
+
+        let view = game.view_for(game.current_player());
+        assert_eq!(choose_action(&game, &view), None);
+    }
+}
@@ -0,0 +1,128 @@
+// Tests for the settings which PreGameBuilder::build accepts.
+
+extern crate kabo;
+
+mod common;
+
+use common::{builder, peek_and_start};
+use kabo::game::{Card, DeckConfig, Player, PlayerIndex, Power, PowerCounts, PreGame, PreGameBuilder,
+                 PreGameError};
+use kabo::messages::Action;
+
+// A deck which is large enough for any number of players.
+fn huge_deck() -> DeckConfig {
+    DeckConfig {
+        counts: [255; 14],
+        red_kings: 0,
+    }
+}
+
+fn many_players(count: usize) -> PreGameBuilder {
+    (0..count)
+        .fold(PreGame::builder(), |builder, index| builder.player(&format!("Player {}", index)))
+        .deck(huge_deck())
+        .seed(3)
+}
+
+#[test]
+fn two_players_with_four_cards_can_be_dealt() {
+    let pre_game = builder(&["Judita", "Sara"], 3).cards_per_player(4).build().unwrap();
+    assert_eq!(pre_game.total_peeks_left(), 4);
+
+    let game = peek_and_start(pre_game, 2);
+    let names: Vec<&str> = game.players().iter().map(Player::name).collect();
+    assert_eq!(names, vec!["Judita", "Sara"]);
+    for player in game.players() {
+        assert_eq!(player.card_count(), 4);
+    }
+}
+
+#[test]
+fn ten_players_with_six_cards_need_more_than_a_standard_deck() {
+    let result = (0..10)
+        .fold(PreGame::builder(), |builder, index| builder.player(&format!("Player {}", index)))
+        .cards_per_player(6)
+        .build();
+    assert_eq!(result.err(), Some(PreGameError::TooManyCards));
+}
+
+#[test]
+fn a_single_player_is_not_enough() {
+    let result = PreGame::builder().player("Judita").build();
+    assert_eq!(result.err(), Some(PreGameError::NotEnoughPlayers));
+}
+
+#[test]
+fn more_peeks_than_fit_in_a_byte_are_rejected() {
+    let result = many_players(100).cards_per_player(3).peeks(3).build();
+    assert_eq!(result.err(), Some(PreGameError::TooManyPeeks));
+
+    let pre_game = many_players(255).cards_per_player(1).peeks(1).build().unwrap();
+    assert_eq!(pre_game.total_peeks_left(), 255);
+}
+
+#[test]
+fn more_players_than_player_indices_are_rejected() {
+    let result = many_players(256).cards_per_player(1).peeks(0).build();
+    assert_eq!(result.err(), Some(PreGameError::TooManyPlayers));
+}
+
+#[test]
+fn a_smaller_deck_is_dealt_and_verified() {
+    let deck = DeckConfig {
+        counts: [2, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 2],
+        red_kings: 1,
+    };
+    assert_eq!(deck.size(), 40);
+
+    let pre_game = builder(&["Judita", "Sara"], 3).deck(deck).build().unwrap();
+    assert!(pre_game.verify_deck_composition());
+    // to_game checks the composition against this deck, not the standard one.
+    let game = peek_and_start(pre_game, 2);
+    assert_eq!(game.deck_size(), 40 - 1 - 2 * 4);
+}
+
+#[test]
+fn a_reduced_deck_has_the_configured_cards() {
+    let deck = DeckConfig {
+        counts: [1, 0, 0, 0, 2, 2, 2, 2, 0, 0, 0, 0, 0, 3],
+        red_kings: 1,
+    };
+    let pre_game = builder(&["Judita", "Sara"], 3).deck(deck.clone()).peeks(4).build().unwrap();
+    // With four peeks each, both players know all of their cards.
+    let mut game = peek_and_start(pre_game, 2);
+    let mut cards: Vec<Card> = game.discard_top().into_iter().collect();
+    for player_index in 0..2 {
+        let view = game.view_for(PlayerIndex(player_index));
+        cards.extend(view.cards[player_index as usize].iter().map(|card| card.unwrap()));
+    }
+    while game.deck_size() > 0 {
+        let player_index = game.current_player();
+        game.apply(player_index, Action::DeckDraw).unwrap();
+        cards.push(game.view_for(player_index).hand_card.unwrap());
+        game.apply(player_index, Action::Discard).unwrap();
+    }
+
+    let mut counts = [0; 14];
+    for card in &cards {
+        counts[card.value() as usize] += 1;
+    }
+    assert_eq!(counts, deck.counts);
+    assert_eq!(cards.iter().filter(|card| card.is_red_king()).count(), 1);
+}
+
+#[test]
+fn power_cards_of_a_huge_deck_are_counted() {
+    let pre_game = many_players(2).peeks(0).build().unwrap();
+    let game = peek_and_start(pre_game, 2);
+
+    // Nobody has seen anything but the top of the discard pile.
+    let top = game.discard_top().unwrap().power();
+    let unseen = |power| 510 - (top == Some(power)) as u16;
+    assert_eq!(game.power_card_estimate(PlayerIndex(0)),
+               PowerCounts {
+                   peek: unseen(Power::Peek),
+                   spy: unseen(Power::Spy),
+                   swap: unseen(Power::Swap),
+               });
+}
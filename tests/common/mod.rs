@@ -0,0 +1,63 @@
+// Helpers shared by the integration tests. Each test file only uses some of them.
+#![allow(dead_code)]
+
+use kabo::ai::choose_action;
+use kabo::game::{CardIndex, Game, GameEvent, PlayerIndex, PreGame, PreGameBuilder};
+#[cfg(feature = "fixtures")]
+use kabo::game::Card;
+
+// A seeded deal for the given players, with the default settings.
+pub fn builder(names: &[&str], seed: u64) -> PreGameBuilder {
+    names.iter().fold(PreGame::builder(), |builder, name| builder.player(name)).seed(seed)
+}
+
+// Each of the players peeks at their first cards until no peeks are left.
+pub fn peek_all(pre_game: &mut PreGame, player_count: u8) {
+    for player_index in 0..player_count {
+        let player_index = PlayerIndex(player_index);
+        for card_index in 0..pre_game.peeks_left(player_index) {
+            pre_game.peek(player_index, CardIndex(card_index)).unwrap();
+        }
+    }
+}
+
+// Each of the players uses up their peeks, then the game starts.
+pub fn peek_and_start(mut pre_game: PreGame, player_count: u8) -> Game {
+    peek_all(&mut pre_game, player_count);
+    pre_game.to_game()
+}
+
+// The computer players play until the game is over.
+pub fn play_to_the_end(game: &mut Game) {
+    for _ in 0..1000 {
+        let player_index = game.current_player();
+        let view = game.view_for(player_index);
+        let action = choose_action(game, &view).expect("The game ended without GameOver.");
+        if game.apply(player_index, action).unwrap().events.contains(&GameEvent::GameOver) {
+            return;
+        }
+    }
+    panic!("The game doesn't end.");
+}
+
+pub fn start_game(names: &[&str], seed: u64) -> Game {
+    peek_and_start(builder(names, seed).build().unwrap(), names.len() as u8)
+}
+
+// Judita against Sara, the game most tests are played with.
+pub fn two_player_game(seed: u64) -> Game {
+    start_game(&["Judita", "Sara"], seed)
+}
+
+#[cfg(feature = "fixtures")]
+pub fn cards(numbers: &[u8]) -> Vec<Card> {
+    numbers.iter().map(|&number| Card::from_value(number)).collect()
+}
+
+// Judita and Sara with the given hands. The discard pile is a single 1.
+#[cfg(feature = "fixtures")]
+pub fn game_with_hands(first: &[u8], second: &[u8], deck: &[u8]) -> Game {
+    Game::from_parts(vec![("Judita".to_owned(), cards(first)), ("Sara".to_owned(), cards(second))],
+                     cards(deck),
+                     cards(&[1]))
+}
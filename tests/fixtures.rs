@@ -0,0 +1,205 @@
+// Tests with hands that are known in advance need the fixtures feature.
+#![cfg(feature = "fixtures")]
+
+extern crate kabo;
+
+mod common;
+
+use common::{cards, game_with_hands};
+use kabo::game::{Card, CardIndex, Game, GameError, GameEvent, PlayerIndex, PowerCounts};
+use kabo::messages::Action;
+
+#[test]
+fn from_parts_deals_the_given_hands() {
+    let game = game_with_hands(&[0, 1, 2, 3], &[13, 12, 10, 5], &[4, 4]);
+
+    assert_eq!(game.current_player(), PlayerIndex(0));
+    assert_eq!(game.kabo_caller(), None);
+    assert!(!game.is_holding_card());
+    assert_eq!(game.score(), vec![("Judita".to_owned(), 6), ("Sara".to_owned(), 40)]);
+}
+
+#[test]
+fn only_stacks_change_the_card_counts() {
+    let mut game = game_with_hands(&[5, 9, 5, 2], &[3, 3, 3, 3], &[5, 6]);
+
+    game.apply(PlayerIndex(0), Action::DeckDraw).unwrap();
+    game.apply(PlayerIndex(0), Action::Replace { card_index: CardIndex(1) }).unwrap();
+    game.apply(PlayerIndex(1), Action::DeckDraw).unwrap();
+    game.apply(PlayerIndex(1), Action::Discard).unwrap();
+    assert_eq!(game.player_card_counts(), vec![4, 4]);
+
+    // Two 5s are discarded and the 5 from the discard pile takes their place.
+    game.apply(PlayerIndex(0), Action::DiscardDraw).unwrap();
+    let stack = Action::MultiReplace {
+        card_type: Card::from_value(5),
+        card_indices: vec![CardIndex(0), CardIndex(2)],
+    };
+    let result = game.apply(PlayerIndex(0), stack).unwrap();
+    assert_eq!(result.discards, cards(&[5, 5]));
+    assert_eq!(game.player_card_counts(), vec![3, 4]);
+}
+
+#[test]
+fn seen_power_cards_are_not_counted() {
+    let mut game = game_with_hands(&[7, 9, 11, 2], &[8, 10, 12, 3], &[7, 12]);
+    let all = PowerCounts { peek: 3, spy: 2, swap: 3 };
+    assert_eq!(game.power_card_estimate(PlayerIndex(0)), all);
+
+    game.apply(PlayerIndex(0), Action::DeckDraw).unwrap();
+    let after_drawing = PowerCounts { swap: 2, ..all };
+    assert_eq!(game.power_card_estimate(PlayerIndex(0)), after_drawing);
+    assert_eq!(game.power_card_estimate(PlayerIndex(1)), all);
+
+    game.apply(PlayerIndex(0), Action::Discard).unwrap();
+    game.apply(PlayerIndex(1), Action::DeckDraw).unwrap();
+    game.apply(PlayerIndex(1), Action::Discard).unwrap();
+    assert_eq!(game.power_card_estimate(PlayerIndex(0)),
+               PowerCounts { peek: 2, ..after_drawing });
+}
+
+// Player 0 calls kabo right away and everybody else just discards a card.
+fn final_scores(hands: &[&[u8]]) -> (Vec<u32>, Option<PlayerIndex>) {
+    let names = ["Judita", "Sara", "Rolf"];
+    let players = names.iter().zip(hands).map(|(name, hand)| (name.to_string(), cards(hand)));
+    let mut game = Game::from_parts(players.collect(), cards(&[4, 4]), cards(&[1]));
+
+    game.apply(PlayerIndex(0), Action::Kabo).unwrap();
+    for player_index in 1..hands.len() as u8 {
+        game.apply(PlayerIndex(player_index), Action::DeckDraw).unwrap();
+        game.apply(PlayerIndex(player_index), Action::Discard).unwrap();
+    }
+
+    let scores = game.score().into_iter().map(|(_, score)| score).collect();
+    (scores, game.winner())
+}
+
+#[test]
+fn only_the_red_king_is_worth_nothing() {
+    let hand = |king: &str| vec![king.parse().unwrap(), Card::from_value(1), Card::from_value(2)];
+    let hands = vec![("Judita".to_owned(), hand("K")), ("Sara".to_owned(), hand("13"))];
+    let game = Game::from_parts(hands, cards(&[4, 5]), cards(&[1]));
+    assert_eq!(game.score(),
+               vec![("Judita".to_owned(), 3), ("Sara".to_owned(), 16)]);
+    assert_eq!(game.winner(), Some(PlayerIndex(0)));
+}
+
+#[test]
+fn the_kabo_caller_wins_with_the_lowest_score() {
+    assert_eq!(final_scores(&[&[0, 1, 2, 3], &[5, 5, 5, 5]]),
+               (vec![6, 20], Some(PlayerIndex(0))));
+}
+
+#[test]
+fn the_kabo_caller_is_penalized_without_the_lowest_score() {
+    assert_eq!(final_scores(&[&[5, 5, 5, 5], &[0, 1, 2, 3]]),
+               (vec![30, 6], Some(PlayerIndex(1))));
+    // A tie isn't good enough either.
+    assert_eq!(final_scores(&[&[0, 1, 2, 3], &[3, 2, 1, 0]]),
+               (vec![16, 6], Some(PlayerIndex(1))));
+}
+
+#[test]
+fn ties_go_to_the_earlier_seat() {
+    assert_eq!(final_scores(&[&[9, 9, 9, 9], &[1, 2, 3, 4], &[4, 3, 2, 1]]),
+               (vec![46, 10, 10], Some(PlayerIndex(1))));
+}
+
+#[test]
+fn drawing_fails_once_both_piles_are_empty() {
+    let mut game = game_with_hands(&[5, 9, 5, 2], &[3, 3, 3, 3], &[]);
+
+    // The wrong snap shuffles the single 1 into the deck to get a penalty card.
+    game.apply(PlayerIndex(1), Action::Snap { card_index: CardIndex(0) }).unwrap();
+    assert_eq!(game.deck_size(), 0);
+    assert_eq!(game.discard_top(), None);
+
+    assert_eq!(game.apply(PlayerIndex(0), Action::DeckDraw).unwrap_err(),
+               GameError::OutOfCards);
+    assert!(!game.is_holding_card());
+}
+
+#[test]
+fn nothing_can_be_drawn_from_the_discard_pile_right_after_a_reshuffle() {
+    let hands = vec![("Judita".to_owned(), cards(&[5, 9, 5, 2])),
+                     ("Sara".to_owned(), cards(&[3, 3, 3, 3]))];
+    let mut game = Game::from_parts(hands, vec![], cards(&[1, 2]));
+
+    // The penalty card of the wrong snap comes from the reshuffled discard pile.
+    let result = game.apply(PlayerIndex(1), Action::Snap { card_index: CardIndex(0) }).unwrap();
+    assert!(result.events.contains(&GameEvent::DiscardShuffle));
+    assert_eq!(game.deck_size(), 1);
+    assert_eq!(game.discard_top(), None);
+
+    assert_eq!(game.apply(PlayerIndex(0), Action::DiscardDraw).unwrap_err(),
+               GameError::EmptyDiscard);
+    game.apply(PlayerIndex(0), Action::DeckDraw).unwrap();
+}
+
+#[test]
+fn only_a_card_from_the_discard_pile_is_known_to_everyone() {
+    let mut game = game_with_hands(&[5, 9, 3, 3], &[3, 3, 3, 3], &[6, 4]);
+
+    game.apply(PlayerIndex(0), Action::DiscardDraw).unwrap();
+    game.apply(PlayerIndex(0), Action::Replace { card_index: CardIndex(0) }).unwrap();
+    let sara = game.view_for(PlayerIndex(1));
+    assert_eq!(sara.cards[0], vec![Some(Card::from_value(1)), None, None, None]);
+
+    game.apply(PlayerIndex(1), Action::DeckDraw).unwrap();
+    game.apply(PlayerIndex(1), Action::Replace { card_index: CardIndex(2) }).unwrap();
+    let judita = game.view_for(PlayerIndex(0));
+    assert_eq!(judita.cards[1], vec![None; 4]);
+    let sara = game.view_for(PlayerIndex(1));
+    assert_eq!(sara.cards[1], vec![None, None, Some(Card::from_value(4)), None]);
+
+    // The same holds for a card from the discard pile which completes a stack.
+    game.apply(PlayerIndex(0), Action::DiscardDraw).unwrap();
+    let stack = Action::MultiReplace {
+        card_type: Card::from_value(3),
+        card_indices: vec![CardIndex(2), CardIndex(3)],
+    };
+    game.apply(PlayerIndex(0), stack).unwrap();
+    let sara = game.view_for(PlayerIndex(1));
+    assert_eq!(sara.cards[0], vec![Some(Card::from_value(1)), None, Some(Card::from_value(3))]);
+}
+
+#[test]
+fn the_snapshot_tells_nothing_about_hidden_cards() {
+    let mut low = game_with_hands(&[0, 1, 2, 3], &[1, 2, 3, 4], &[6, 0]);
+    let mut high = game_with_hands(&[13, 12, 11, 10], &[9, 8, 7, 6], &[6, 13]);
+    assert_eq!(low.snapshot(), high.snapshot());
+
+    // Not even the hand card of the current player shows up.
+    low.apply(PlayerIndex(0), Action::DeckDraw).unwrap();
+    high.apply(PlayerIndex(0), Action::DeckDraw).unwrap();
+    assert_ne!(low.view_for(PlayerIndex(0)).hand_card, high.view_for(PlayerIndex(0)).hand_card);
+    assert_eq!(low.snapshot(), high.snapshot());
+    assert!(low.snapshot().holding_card);
+}
+
+#[test]
+fn the_history_lists_the_public_events_in_order() {
+    let mut game = game_with_hands(&[5, 9, 5, 2], &[3, 3, 3, 3], &[6, 7]);
+
+    game.apply(PlayerIndex(0), Action::DeckDraw).unwrap();
+    game.apply(PlayerIndex(0), Action::Peek { card_index: CardIndex(1) }).unwrap();
+    game.apply(PlayerIndex(1), Action::DeckDraw).unwrap();
+    game.apply(PlayerIndex(1), Action::Discard).unwrap();
+    game.apply(PlayerIndex(0), Action::DiscardDraw).unwrap();
+    game.apply(PlayerIndex(0), Action::Replace { card_index: CardIndex(0) }).unwrap();
+
+    // Neither the drawn cards nor the peeked 9 show up.
+    let turn = |discard, next_player| {
+        vec![GameEvent::Discards { cards: cards(&[discard]) },
+             GameEvent::EndTurn { next_player: PlayerIndex(next_player) },
+             GameEvent::StartTurn { player_index: PlayerIndex(next_player) }]
+    };
+    let mut expected = vec![GameEvent::StartTurn { player_index: PlayerIndex(0) }];
+    expected.extend(turn(7, 1));
+    expected.extend(turn(6, 0));
+    expected.extend(turn(5, 1));
+    assert_eq!(game.history(), &expected[..]);
+    assert!(!game.history()
+        .iter()
+        .any(|event| matches!(*event, GameEvent::CardDrawn { .. } | GameEvent::Seen { .. })));
+}
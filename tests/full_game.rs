@@ -0,0 +1,160 @@
+// Plays a complete seeded game through Game::apply, from the peeks to the scores.
+
+extern crate kabo;
+
+mod common;
+
+use kabo::ai::choose_action;
+use kabo::game::{CardIndex, Game, GameError, GameEvent, PlayerIndex};
+use kabo::messages::Action;
+
+const SEED: u64 = 42;
+
+fn play(game: &mut Game, player_index: u8, action: Action) -> Vec<GameEvent> {
+    game.apply(PlayerIndex(player_index), action.clone())
+        .unwrap_or_else(|error| panic!("{:?} failed: {}", action, error))
+        .events
+}
+
+fn start_game() -> Game {
+    common::start_game(&["Judita", "Sara", "Rolf"], SEED)
+}
+
+#[test]
+fn scripted_game() {
+    let mut game = start_game();
+    assert_eq!(game.history(),
+               &[GameEvent::StartTurn { player_index: PlayerIndex(0) }]);
+
+    play(&mut game, 0, Action::DeckDraw);
+    let events = play(&mut game, 0, Action::Replace { card_index: CardIndex(0) });
+    assert_eq!(&events[1..],
+               &[GameEvent::EndTurn { next_player: PlayerIndex(1) },
+                 GameEvent::StartTurn { player_index: PlayerIndex(1) }]);
+
+    play(&mut game, 1, Action::DeckDraw);
+    play(&mut game, 1, Action::Discard);
+
+    play(&mut game, 2, Action::DeckDraw);
+    play(&mut game, 2, Action::Replace { card_index: CardIndex(1) });
+
+    let events = play(&mut game, 0, Action::Kabo);
+    assert_eq!(events[0], GameEvent::Kabo { player_index: PlayerIndex(0) });
+    assert_eq!(game.kabo_caller(), Some(PlayerIndex(0)));
+
+    // Everybody else gets one final turn.
+    play(&mut game, 1, Action::DeckDraw);
+    play(&mut game, 1, Action::Replace { card_index: CardIndex(2) });
+    play(&mut game, 2, Action::DeckDraw);
+    let events = play(&mut game, 2, Action::Discard);
+    assert_eq!(events.last(), Some(&GameEvent::GameOver));
+
+    assert_eq!(game.apply(PlayerIndex(0), Action::DeckDraw).unwrap_err(),
+               GameError::GameFinished);
+    // Judita called kabo without having the lowest score, which costs 10 points.
+    assert_eq!(game.score(),
+               vec![("Judita".to_owned(), 37), ("Sara".to_owned(), 32), ("Rolf".to_owned(), 21)]);
+    assert_eq!(game.winner(), Some(PlayerIndex(2)));
+}
+
+fn scripted_actions() -> Vec<(PlayerIndex, Action)> {
+    vec![(PlayerIndex(0), Action::DeckDraw),
+         (PlayerIndex(0), Action::Discard),
+         (PlayerIndex(1), Action::Kabo),
+         (PlayerIndex(2), Action::DeckDraw),
+         (PlayerIndex(2), Action::Discard),
+         (PlayerIndex(0), Action::DeckDraw),
+         (PlayerIndex(0), Action::Replace { card_index: CardIndex(3) })]
+}
+
+#[test]
+fn replay_is_deterministic() {
+    let actions = scripted_actions();
+    let first = start_game().replay(&actions).unwrap();
+    let second = start_game().replay(&actions).unwrap();
+    assert_eq!(first.history().last(), Some(&GameEvent::GameOver));
+    assert_eq!(first.score(), second.score());
+    assert_eq!(first.history(), second.history());
+
+    // Judita's turn after Sara's kabo is the last one. Sara doesn't have the
+    // lowest score, so she gets the 10 point penalty.
+    assert_eq!(first.current_player(), PlayerIndex(0));
+    assert_eq!(first.score(),
+               vec![("Judita".to_owned(), 16), ("Sara".to_owned(), 37), ("Rolf".to_owned(), 20)]);
+    assert_eq!(first.winner(), Some(PlayerIndex(0)));
+}
+
+#[test]
+fn players_who_are_out_are_skipped() {
+    let mut game = start_game();
+    game.mark_out(PlayerIndex(1)).unwrap();
+
+    play(&mut game, 0, Action::DeckDraw);
+    let events = play(&mut game, 0, Action::Discard);
+    assert_eq!(events.last(), Some(&GameEvent::StartTurn { player_index: PlayerIndex(2) }));
+
+    // Rolf is the last one left, so the game ends with his turn.
+    game.mark_out(PlayerIndex(0)).unwrap();
+    play(&mut game, 2, Action::DeckDraw);
+    let events = play(&mut game, 2, Action::Discard);
+    assert_eq!(events.last(), Some(&GameEvent::GameOver));
+}
+
+#[test]
+fn undo_keeps_players_out() {
+    let mut game = start_game();
+    play(&mut game, 0, Action::DeckDraw);
+    game.mark_out(PlayerIndex(1)).unwrap();
+
+    // Only the draw is undone, Sara stays out.
+    game.undo().unwrap();
+    assert!(game.is_out(PlayerIndex(1)));
+    assert!(!game.is_holding_card());
+    play(&mut game, 0, Action::DeckDraw);
+    let events = play(&mut game, 0, Action::Discard);
+    assert_eq!(events.last(), Some(&GameEvent::StartTurn { player_index: PlayerIndex(2) }));
+}
+
+#[test]
+fn one_event_buffer_is_enough_for_a_replay() {
+    let mut game = start_game();
+    let mut events = Vec::with_capacity(8);
+    let buffer = events.as_ptr();
+    for (player_index, action) in scripted_actions() {
+        events.clear();
+        game.apply_into(player_index, action, &mut events).unwrap();
+        assert_eq!(events.as_ptr(), buffer);
+    }
+
+    let replayed = start_game().replay(&scripted_actions()).unwrap();
+    assert_eq!(replayed.history(), game.history());
+}
+
+#[test]
+fn no_card_is_created_or_lost_in_a_long_game() {
+    for seed in 0..20 {
+        let mut game = common::start_game(&["Judita", "Sara", "Rolf", "Mia"], seed);
+        assert_eq!(game.total_cards(), 52);
+
+        // With the penalty cards, the computer players rarely call kabo, which
+        // makes for long games.
+        for step in 0..300 {
+            let player_index = game.current_player();
+            // Snaps from the other players, most of them wrong, also move cards around.
+            // Each wrong one takes a card out of the deck, so they stop before it runs out.
+            if step % 3 == 0 && game.deck_size() > 10 {
+                let snapping = PlayerIndex((player_index.0 + 1) % 4);
+                let _ = game.apply(snapping, Action::Snap { card_index: CardIndex(0) });
+                assert_eq!(game.total_cards(), 52, "seed {}", seed);
+            }
+
+            let view = game.view_for(player_index);
+            let action = choose_action(&game, &view).unwrap();
+            let events = play(&mut game, player_index.0, action);
+            assert_eq!(game.total_cards(), 52, "seed {}", seed);
+            if events.contains(&GameEvent::GameOver) {
+                break;
+            }
+        }
+    }
+}
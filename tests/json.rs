@@ -0,0 +1,38 @@
+// Sending messages as JSON needs the serde feature.
+#![cfg(feature = "serde")]
+
+extern crate kabo;
+extern crate serde_json;
+
+use kabo::game::{Card, CardIndex, PlayerIndex};
+use kabo::messages::Action;
+
+#[test]
+fn every_action_survives_a_round_trip() {
+    let actions = vec![Action::DeckDraw,
+                       Action::DiscardDraw,
+                       Action::Kabo,
+                       Action::Replace { card_index: CardIndex(2) },
+                       Action::MultiReplace {
+                           card_type: "K".parse::<Card>().unwrap(),
+                           card_indices: vec![CardIndex(0), CardIndex(3)],
+                       },
+                       Action::Peek { card_index: CardIndex(1) },
+                       Action::Spy {
+                           other_player_index: PlayerIndex(1),
+                           card_index: CardIndex(0),
+                       },
+                       Action::Swap {
+                           my_card_index: CardIndex(3),
+                           other_player_index: PlayerIndex(2),
+                           other_card_index: CardIndex(1),
+                       },
+                       Action::Discard,
+                       Action::Snap { card_index: CardIndex(2) }];
+
+    for action in actions {
+        let json = serde_json::to_string(&action).unwrap();
+        let parsed: Action = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, action, "{}", json);
+    }
+}
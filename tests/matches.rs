@@ -0,0 +1,63 @@
+// Plays matches of several rounds with the computer players.
+
+extern crate kabo;
+
+mod common;
+
+use common::{builder, peek_all, play_to_the_end};
+use kabo::game::{Game, PlayerIndex};
+use kabo::matches::Match;
+
+fn play_round(kabo_match: &mut Match) -> (Game, bool) {
+    let mut pre_game = kabo_match.next_round().unwrap();
+    peek_all(&mut pre_game, 2);
+    let mut game = kabo_match.start_round(pre_game);
+    let first_player = game.current_player();
+    play_to_the_end(&mut game);
+    assert_eq!(first_player, kabo_match.first_player());
+
+    let over = kabo_match.finish_round(game.clone());
+    (game, over)
+}
+
+#[test]
+fn the_totals_add_up_over_two_rounds() {
+    let mut kabo_match = Match::from_builder(builder(&["Judita", "Sara"], 5), 1000);
+    assert_eq!(kabo_match.first_player(), PlayerIndex(0));
+
+    let (first, over) = play_round(&mut kabo_match);
+    assert!(!over);
+    assert_eq!(kabo_match.first_player(), PlayerIndex(1));
+    let (second, over) = play_round(&mut kabo_match);
+    assert!(!over);
+    assert_eq!(kabo_match.first_player(), PlayerIndex(0));
+
+    let totals: Vec<(String, u32)> = first.score()
+        .into_iter()
+        .zip(second.score())
+        .map(|((name, first), (_, second))| (name, first + second))
+        .collect();
+    assert_eq!(kabo_match.scores(), &totals[..]);
+}
+
+#[test]
+fn the_match_is_over_once_a_total_crosses_the_threshold() {
+    let mut kabo_match = Match::from_builder(builder(&["Judita", "Sara"], 5), 0);
+    let (game, over) = play_round(&mut kabo_match);
+    assert_eq!(over, game.score().iter().any(|&(_, score)| score > 0));
+    assert_eq!(kabo_match.is_over(), over);
+}
+
+#[test]
+fn every_round_is_dealt_with_the_settings_of_the_builder() {
+    let settings = builder(&["Judita", "Sara"], 5).cards_per_player(6).peeks(3);
+    let kabo_match = Match::from_builder(settings, 100);
+    let mut pre_game = kabo_match.next_round().unwrap();
+    assert_eq!(pre_game.total_peeks_left(), 6);
+
+    peek_all(&mut pre_game, 2);
+    let game = kabo_match.start_round(pre_game);
+    for player in game.players() {
+        assert_eq!(player.card_count(), 6);
+    }
+}
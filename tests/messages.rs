@@ -0,0 +1,54 @@
+// Checks how the results of actions are turned into messages for the players.
+
+extern crate kabo;
+
+mod common;
+
+use common::two_player_game;
+use kabo::game::{CardIndex, GameError, PlayerIndex, Visibility};
+use kabo::messages::{to_server_messages, Action, Server};
+
+#[test]
+fn a_discard_is_announced_to_everyone() {
+    let mut game = two_player_game(7);
+    game.deck_draw().unwrap();
+    let drawn = game.view_for(PlayerIndex(0)).hand_card.unwrap();
+
+    let result = game.discard();
+    let messages = to_server_messages(&game, PlayerIndex(0), &Action::Discard, &result);
+    let success = Server::ActionSuccess {
+        player_index: PlayerIndex(0),
+        action: Action::Discard,
+        discards: vec![drawn],
+    };
+    assert_eq!(messages[0], (Visibility::Public, success));
+    let start = Server::StartTurn { player_index: PlayerIndex(1) };
+    assert!(messages.contains(&(Visibility::Public, start)));
+}
+
+#[test]
+fn an_error_is_only_sent_to_the_player_who_acted() {
+    let mut game = two_player_game(7);
+
+    let result = game.discard();
+    let messages = to_server_messages(&game, PlayerIndex(0), &Action::Discard, &result);
+    let error = Server::Error { error: GameError::WrongPhase };
+    assert_eq!(messages,
+               vec![(Visibility::Private { recipient: PlayerIndex(0) }, error)]);
+}
+
+#[test]
+fn a_swap_names_every_index_with_its_type() {
+    let swap = Action::Swap {
+        my_card_index: CardIndex(3),
+        other_player_index: PlayerIndex(1),
+        other_card_index: CardIndex(0),
+    };
+    // Plain numbers convert into the right index, the field names say which is which.
+    let converted = Action::Swap {
+        my_card_index: 3.into(),
+        other_player_index: 1.into(),
+        other_card_index: 0.into(),
+    };
+    assert_eq!(swap, converted);
+}
@@ -0,0 +1,236 @@
+// The powers of the 7 to 12, with hands that are known in advance.
+#![cfg(feature = "fixtures")]
+
+extern crate kabo;
+
+mod common;
+
+use common::game_with_hands;
+use kabo::game::{Card, CardIndex, Game, GameError, GameEvent, PlayerIndex, Visibility};
+use kabo::messages::Action;
+
+// Judita holds the given card, which she drew from the deck.
+fn holding(card: u8) -> Game {
+    let mut game = game_with_hands(&[0, 1, 2, 3], &[13, 12, 10, 5], &[4, card]);
+    game.apply(PlayerIndex(0), Action::DeckDraw).unwrap();
+    game
+}
+
+fn peek(card_index: u8) -> Action {
+    Action::Peek { card_index: CardIndex(card_index) }
+}
+
+#[test]
+fn a_7_peeks_at_an_own_card() {
+    let mut game = holding(7);
+
+    let events = game.apply(PlayerIndex(0), peek(2)).unwrap().events;
+    assert_eq!(events,
+               vec![GameEvent::Seen {
+                        recipient: PlayerIndex(0),
+                        player_index: PlayerIndex(0),
+                        card_index: CardIndex(2),
+                        card: Card::from_value(2),
+                    },
+                    GameEvent::Discards { cards: vec![Card::from_value(7)] },
+                    GameEvent::EndTurn { next_player: PlayerIndex(1) },
+                    GameEvent::StartTurn { player_index: PlayerIndex(1) }]);
+    assert_eq!(game.known_card(PlayerIndex(0), CardIndex(2)), Some(Card::from_value(2)));
+}
+
+#[test]
+fn an_8_peeks_as_well() {
+    let mut game = holding(8);
+
+    let result = game.apply(PlayerIndex(0), peek(0)).unwrap();
+    assert_eq!(result.discards, vec![Card::from_value(8)]);
+    assert_eq!(game.known_card(PlayerIndex(0), CardIndex(0)), Some(Card::from_value(0)));
+    assert_eq!(game.known_card(PlayerIndex(0), CardIndex(1)), None);
+}
+
+#[test]
+fn a_6_has_no_peek() {
+    let mut game = holding(6);
+
+    assert_eq!(game.apply(PlayerIndex(0), peek(0)).unwrap_err(), GameError::WrongCard);
+    assert!(game.is_holding_card());
+}
+
+#[test]
+fn a_peek_needs_an_existing_card() {
+    let mut game = holding(7);
+
+    assert_eq!(game.apply(PlayerIndex(0), peek(4)).unwrap_err(), GameError::InvalidIndex);
+    assert!(game.is_holding_card());
+}
+
+fn spy(other_player_index: u8, card_index: u8) -> Action {
+    Action::Spy {
+        other_player_index: PlayerIndex(other_player_index),
+        card_index: CardIndex(card_index),
+    }
+}
+
+#[test]
+fn a_9_spies_on_another_player() {
+    let mut game = holding(9);
+
+    let events = game.apply(PlayerIndex(0), spy(1, 1)).unwrap().events;
+    assert_eq!(events[0],
+               GameEvent::Seen {
+                   recipient: PlayerIndex(0),
+                   player_index: PlayerIndex(1),
+                   card_index: CardIndex(1),
+                   card: Card::from_value(12),
+               });
+    assert_eq!(game.view_for(PlayerIndex(0)).cards[1],
+               vec![None, Some(Card::from_value(12)), None, None]);
+    assert_eq!(game.known_card(PlayerIndex(1), CardIndex(1)), None);
+}
+
+#[test]
+fn nobody_spies_on_themselves() {
+    let mut game = holding(10);
+
+    assert_eq!(game.apply(PlayerIndex(0), spy(0, 1)).unwrap_err(), GameError::InvalidIndex);
+    assert!(game.is_holding_card());
+}
+
+#[test]
+fn a_spy_needs_an_existing_player() {
+    let mut game = holding(10);
+
+    assert_eq!(game.apply(PlayerIndex(0), spy(2, 0)).unwrap_err(), GameError::InvalidIndex);
+    assert!(game.is_holding_card());
+}
+
+fn swap(my_card_index: u8, other_player_index: u8, other_card_index: u8) -> Action {
+    Action::Swap {
+        my_card_index: CardIndex(my_card_index),
+        other_player_index: PlayerIndex(other_player_index),
+        other_card_index: CardIndex(other_card_index),
+    }
+}
+
+#[test]
+fn an_11_swaps_cards_with_another_player() {
+    let mut game = holding(11);
+
+    let events = game.apply(PlayerIndex(0), swap(0, 1, 3)).unwrap().events;
+    assert_eq!(events[0],
+               GameEvent::Swapped {
+                   player_index: PlayerIndex(0),
+                   card_index: CardIndex(0),
+                   other_player_index: PlayerIndex(1),
+                   other_card_index: CardIndex(3),
+               });
+    // The 0 of Judita and the 5 of Sara changed places.
+    assert_eq!(game.score(), vec![("Judita".to_owned(), 11), ("Sara".to_owned(), 35)]);
+    assert_eq!(game.player_card_counts(), vec![4, 4]);
+}
+
+#[test]
+fn nobody_swaps_with_themselves() {
+    let mut game = holding(12);
+
+    assert_eq!(game.apply(PlayerIndex(0), swap(0, 0, 1)).unwrap_err(), GameError::InvalidIndex);
+    assert!(game.is_holding_card());
+}
+
+#[test]
+fn a_swap_needs_existing_cards() {
+    let mut game = holding(12);
+
+    for action in [swap(4, 1, 0), swap(0, 1, 4), swap(0, 2, 0)] {
+        assert_eq!(game.apply(PlayerIndex(0), action).unwrap_err(), GameError::InvalidIndex);
+    }
+    assert!(game.is_holding_card());
+    assert_eq!(game.score(), vec![("Judita".to_owned(), 6), ("Sara".to_owned(), 40)]);
+}
+
+#[test]
+fn every_power_rejects_a_card_index_past_the_hand() {
+    let actions = [(7, peek(4)),
+                   (8, peek(255)),
+                   (9, spy(1, 4)),
+                   (10, spy(1, 255)),
+                   (11, swap(4, 1, 0)),
+                   (12, swap(0, 1, 255))];
+    for (card, action) in actions {
+        let mut game = holding(card);
+        let before = game.view_for(PlayerIndex(0));
+
+        assert_eq!(game.apply(PlayerIndex(0), action.clone()).unwrap_err(),
+                   GameError::InvalidIndex,
+                   "{:?}",
+                   action);
+        assert_eq!(game.view_for(PlayerIndex(0)), before);
+    }
+}
+
+// Judita may discard a card from the deck, replace any of her cards with it or
+// stack it on a pair.
+fn actions_without_power(card: u8) -> Vec<Action> {
+    let mut actions = vec![Action::Discard];
+    actions.extend((0..4).map(|card_index| Action::Replace { card_index: CardIndex(card_index) }));
+    for first in 0..4 {
+        for second in (first + 1)..4 {
+            actions.push(Action::MultiReplace {
+                card_type: Card::from_value(card),
+                card_indices: vec![CardIndex(first), CardIndex(second)],
+            });
+        }
+    }
+    actions
+}
+
+#[test]
+fn only_a_power_card_offers_its_power() {
+    let mut with_peeks = actions_without_power(7);
+    with_peeks.extend((0..4).map(peek));
+    assert_eq!(holding(7).legal_actions(), with_peeks);
+
+    assert_eq!(holding(3).legal_actions(), actions_without_power(3));
+}
+
+#[test]
+fn only_the_spying_player_sees_the_card() {
+    let mut game = game_with_hands(&[0, 1, 2, 3], &[13, 12, 10, 5], &[9, 4]);
+    game.apply(PlayerIndex(0), Action::DeckDraw).unwrap();
+    game.apply(PlayerIndex(0), Action::Discard).unwrap();
+
+    // Sara spies on Judita, so Sara is the one who learns the card.
+    game.apply(PlayerIndex(1), Action::DeckDraw).unwrap();
+    let events = game.apply(PlayerIndex(1), spy(0, 2)).unwrap().events;
+    let seen = GameEvent::Seen {
+        recipient: PlayerIndex(1),
+        player_index: PlayerIndex(0),
+        card_index: CardIndex(2),
+        card: Card::from_value(2),
+    };
+    assert_eq!(events[0], seen);
+    assert_eq!(seen.visibility(), Visibility::Private { recipient: PlayerIndex(1) });
+    assert_eq!(game.view_for(PlayerIndex(1)).cards[0],
+               vec![None, None, Some(Card::from_value(2)), None]);
+    assert_eq!(game.known_card(PlayerIndex(0), CardIndex(2)), None);
+}
+
+#[test]
+fn a_spy_has_one_private_event() {
+    let mut game = holding(9);
+
+    let events = game.apply(PlayerIndex(0), spy(1, 0)).unwrap().events;
+    let (public, private): (Vec<GameEvent>, Vec<GameEvent>) =
+        events.into_iter().partition(|event| event.visibility() == Visibility::Public);
+    assert_eq!(private,
+               vec![GameEvent::Seen {
+                        recipient: PlayerIndex(0),
+                        player_index: PlayerIndex(1),
+                        card_index: CardIndex(0),
+                        card: Card::from_value(13),
+                    }]);
+    assert_eq!(public,
+               vec![GameEvent::Discards { cards: vec![Card::from_value(9)] },
+                    GameEvent::EndTurn { next_player: PlayerIndex(1) },
+                    GameEvent::StartTurn { player_index: PlayerIndex(1) }]);
+}
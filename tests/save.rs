@@ -0,0 +1,69 @@
+// Saving and loading a game needs the serde feature.
+#![cfg(feature = "serde")]
+
+extern crate kabo;
+extern crate serde_json;
+
+mod common;
+
+use common::{builder, two_player_game};
+use kabo::game::{Card, CardIndex, Game, PlayerIndex, PreGame};
+use kabo::messages::Action;
+
+#[test]
+fn a_loaded_game_continues_like_the_original() {
+    let mut game = two_player_game(3);
+    game.apply(PlayerIndex(0), Action::DeckDraw).unwrap();
+    game.apply(PlayerIndex(0), Action::Replace { card_index: CardIndex(2) }).unwrap();
+    game.apply(PlayerIndex(1), Action::DeckDraw).unwrap();
+
+    let json = game.to_json();
+    let mut loaded = Game::from_json(&json).unwrap();
+    assert_eq!(loaded.to_json(), json);
+    assert_eq!(loaded.snapshot(), game.snapshot());
+    assert_eq!(loaded.view_for(PlayerIndex(1)), game.view_for(PlayerIndex(1)));
+
+    // The deck order was kept, so both games draw the same cards.
+    for game in [&mut game, &mut loaded] {
+        game.apply(PlayerIndex(1), Action::Replace { card_index: CardIndex(0) }).unwrap();
+        game.apply(PlayerIndex(0), Action::DeckDraw).unwrap();
+    }
+    assert_eq!(loaded.view_for(PlayerIndex(0)), game.view_for(PlayerIndex(0)));
+}
+
+#[test]
+fn cards_are_written_as_numbers_and_the_red_king_as_k() {
+    let red_king: Card = "K".parse().unwrap();
+    let black_king: Card = "13".parse().unwrap();
+    assert_eq!(serde_json::to_string(&red_king).unwrap(), r#""K""#);
+    assert_eq!(serde_json::to_string(&black_king).unwrap(), "13");
+    assert_eq!(serde_json::to_string(&Card::try_new(7).unwrap()).unwrap(), "7");
+
+    assert_eq!(serde_json::from_str::<Card>(r#""K""#).unwrap(), red_king);
+    assert_eq!(serde_json::from_str::<Card>("13").unwrap(), black_king);
+    assert!(!serde_json::from_str::<Card>("13").unwrap().is_red_king());
+}
+
+#[test]
+fn invalid_cards_are_not_deserialized() {
+    assert!(serde_json::from_str::<Card>("99").is_err());
+    assert!(serde_json::from_str::<Card>(r#""seven""#).is_err());
+    assert!(serde_json::from_str::<Card>(r#"{"number":7,"red":false}"#).is_err());
+}
+
+#[test]
+fn a_corrupted_deal_is_detected() {
+    let pre_game = builder(&["Judita", "Sara"], 3).build().unwrap();
+    let mut json = serde_json::to_value(&pre_game).unwrap();
+    // Turn a 0 into a 5, so the deck still has 52 cards.
+    let zero = json["deck"]
+        .as_array_mut()
+        .unwrap()
+        .iter_mut()
+        .find(|card| **card == 0)
+        .unwrap();
+    *zero = 5.into();
+
+    let corrupted: PreGame = serde_json::from_value(json).unwrap();
+    assert!(!corrupted.verify_deck_composition());
+}
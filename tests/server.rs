@@ -0,0 +1,67 @@
+// Plays through the TCP server on a loopback socket.
+#![cfg(feature = "serde")]
+
+extern crate kabo;
+extern crate serde_json;
+
+mod common;
+
+use common::two_player_game;
+use kabo::game::{GameError, PlayerIndex};
+use kabo::messages::{Action, Player, Server};
+use kabo::server::serve;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+
+// One player's connection, which sends and receives JSON lines.
+struct Client {
+    stream: TcpStream,
+    reader: BufReader<TcpStream>,
+}
+
+impl Client {
+    fn connect(listener: &TcpListener) -> Self {
+        let stream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let reader = BufReader::new(stream.try_clone().unwrap());
+        Client { stream, reader }
+    }
+    fn send_line(&mut self, line: &str) {
+        self.stream.write_all(line.as_bytes()).unwrap();
+        self.stream.write_all(b"\n").unwrap();
+    }
+    fn send(&mut self, message: &Player) {
+        let line = serde_json::to_string(message).unwrap();
+        self.send_line(&line);
+    }
+    fn recv(&mut self) -> Server {
+        let mut line = String::new();
+        self.reader.read_line(&mut line).unwrap();
+        serde_json::from_str(&line).expect("The server sent no valid message.")
+    }
+}
+
+#[test]
+fn a_draw_over_tcp() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let mut judita = Client::connect(&listener);
+    let sara = Client::connect(&listener);
+    let server = thread::spawn(move || serve(listener, two_player_game(7)));
+
+    assert_eq!(judita.recv(), Server::StartTurn { player_index: PlayerIndex(0) });
+    judita.send(&Player::Play { action: Action::DeckDraw });
+    loop {
+        if let Server::CardDrawn { .. } = judita.recv() {
+            break;
+        }
+    }
+
+    // A line which is no message is answered, and the game goes on.
+    judita.send_line("{\"Play\": ");
+    assert_eq!(judita.recv(), Server::Error { error: GameError::MalformedMessage });
+
+    // Once every player is gone, the server stops.
+    drop(judita);
+    drop(sara);
+    server.join().unwrap().unwrap();
+}
@@ -0,0 +1,109 @@
+// Stacks of equal cards, with hands that are known in advance.
+#![cfg(feature = "fixtures")]
+
+extern crate kabo;
+
+mod common;
+
+use common::{cards, game_with_hands};
+use kabo::game::{Card, CardIndex, GameError, GameEvent, PlayerIndex, Visibility};
+use kabo::messages::{to_server_messages, Action, Server};
+
+fn stack(card_type: u8, card_indices: &[u8]) -> Action {
+    Action::MultiReplace {
+        card_type: Card::from_value(card_type),
+        card_indices: card_indices.iter().map(|&index| CardIndex(index)).collect(),
+    }
+}
+
+#[test]
+fn a_matching_stack_is_discarded() {
+    let mut game = game_with_hands(&[5, 9, 5, 2], &[3, 3, 3, 3], &[6, 5]);
+
+    game.apply(PlayerIndex(0), Action::DeckDraw).unwrap();
+    let result = game.apply(PlayerIndex(0), stack(5, &[0, 2])).unwrap();
+    assert_eq!(result.discards, cards(&[5, 5]));
+    assert_eq!(game.player_card_counts(), vec![3, 4]);
+    // The drawn 5 took the place of the first card.
+    assert_eq!(game.known_card(PlayerIndex(0), CardIndex(0)), Some(Card::from_value(5)));
+    assert_eq!(game.current_player(), PlayerIndex(1));
+}
+
+#[test]
+fn a_wrong_stack_costs_a_penalty_card() {
+    let mut game = game_with_hands(&[5, 9, 5, 2], &[3, 3, 3, 3], &[6, 5]);
+
+    game.apply(PlayerIndex(0), Action::DeckDraw).unwrap();
+    let result = game.apply(PlayerIndex(0), stack(5, &[0, 1])).unwrap();
+    // The hand card is discarded and the 6 from the deck is the penalty.
+    assert_eq!(result.discards, cards(&[5]));
+    assert_eq!(game.deck_size(), 0);
+    assert_eq!(game.current_player(), PlayerIndex(1));
+    assert_eq!(result.events.last(),
+               Some(&GameEvent::StartTurn { player_index: PlayerIndex(1) }));
+}
+
+#[test]
+fn a_stack_must_not_name_a_card_twice() {
+    let mut game = game_with_hands(&[5, 9, 5, 2], &[3, 3, 3, 3], &[6, 5]);
+
+    game.apply(PlayerIndex(0), Action::DeckDraw).unwrap();
+    assert_eq!(game.apply(PlayerIndex(0), stack(5, &[0, 0])).unwrap_err(),
+               GameError::InvalidIndex);
+    assert_eq!(game.apply(PlayerIndex(0), stack(5, &[0, 2, 0])).unwrap_err(),
+               GameError::InvalidIndex);
+    // Nothing happened, Judita still holds the 5.
+    assert!(game.is_holding_card());
+    assert_eq!(game.player_card_counts(), vec![4, 4]);
+    assert_eq!(game.view_for(PlayerIndex(0)).hand_card, Some(Card::from_value(5)));
+}
+
+#[test]
+fn a_matching_snap_shrinks_the_hand() {
+    let mut game = game_with_hands(&[5, 9, 5, 2], &[3, 3, 1, 3], &[6, 5]);
+
+    // Sara may snap during the turn of Judita.
+    let result = game.apply(PlayerIndex(1), Action::Snap { card_index: CardIndex(2) }).unwrap();
+    assert_eq!(result.discards, cards(&[1]));
+    assert_eq!(game.player_card_counts(), vec![4, 3]);
+    assert_eq!(game.discard_top(), Some(Card::from_value(1)));
+    assert_eq!(game.current_player(), PlayerIndex(0));
+}
+
+#[test]
+fn a_wrong_snap_costs_a_penalty_card() {
+    let mut game = game_with_hands(&[5, 9, 5, 2], &[3, 3, 1, 3], &[6, 5]);
+
+    let result = game.apply(PlayerIndex(1), Action::Snap { card_index: CardIndex(0) }).unwrap();
+    assert_eq!(result.events[0],
+               GameEvent::SnapFailure {
+                   player_index: PlayerIndex(1),
+                   card_index: CardIndex(0),
+                   card: Card::from_value(3),
+               });
+    assert_eq!(game.player_card_counts(), vec![4, 5]);
+    assert_eq!(game.deck_size(), 1);
+    // The 3 stays in the hand, but now everyone knows it.
+    assert_eq!(game.view_for(PlayerIndex(0)).cards[1][0], Some(Card::from_value(3)));
+    assert_eq!(game.discard_top(), Some(Card::from_value(1)));
+}
+
+#[test]
+fn a_wrong_stack_shows_the_claimed_cards_to_everyone() {
+    let mut game = game_with_hands(&[5, 9, 5, 2], &[3, 3, 3, 3], &[6, 5]);
+
+    game.apply(PlayerIndex(0), Action::DeckDraw).unwrap();
+    let action = stack(5, &[0, 1]);
+    let result = game.apply(PlayerIndex(0), action.clone()).map(|result| result.events);
+    // The claimed cards stay and the penalty card is added.
+    assert_eq!(game.player_card_counts(), vec![5, 4]);
+
+    let failure = Server::MultiReplaceFailure {
+        player_index: PlayerIndex(0),
+        card_type_claimed: Card::from_value(5),
+        cards_seen: vec![(CardIndex(0), Card::from_value(5)), (CardIndex(1), Card::from_value(9))],
+    };
+    let messages = to_server_messages(&game, PlayerIndex(0), &action, &result);
+    assert!(messages.contains(&(Visibility::Public, failure)));
+    assert_eq!(game.view_for(PlayerIndex(1)).cards[0][1], Some(Card::from_value(9)));
+}
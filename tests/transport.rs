@@ -0,0 +1,52 @@
+// Plays through the game loop without any sockets.
+
+extern crate kabo;
+
+mod common;
+
+use common::two_player_game;
+use kabo::game::PlayerIndex;
+use kabo::messages::{Action, Player, Server};
+use kabo::transport::{channel_transport, play, ChannelClient};
+use std::thread;
+
+// Skips the messages before the first one the predicate accepts.
+fn wait_for<F>(client: &ChannelClient, accept: F) -> Server
+    where F: Fn(&Server) -> bool
+{
+    loop {
+        let message = client.recv().expect("The game loop ended too early.");
+        if accept(&message) {
+            return message;
+        }
+    }
+}
+
+#[test]
+fn a_turn_through_the_channel_transport() {
+    let game = two_player_game(7);
+    let (mut transport, clients) = channel_transport(2);
+    let server = thread::spawn(move || play(&mut transport, game));
+
+    let judita = &clients[0];
+    assert_eq!(judita.recv(),
+               Some(Server::StartTurn { player_index: PlayerIndex(0) }));
+    assert!(judita.send(Player::Play { action: Action::DeckDraw }));
+    let drawn = match wait_for(judita, |message| matches!(*message, Server::CardDrawn { .. })) {
+        Server::CardDrawn { card } => card,
+        _ => unreachable!(),
+    };
+
+    assert!(judita.send(Player::Play { action: Action::Discard }));
+    let success = Server::ActionSuccess {
+        player_index: PlayerIndex(0),
+        action: Action::Discard,
+        discards: vec![drawn],
+    };
+    assert_eq!(wait_for(judita, |message| matches!(*message, Server::ActionSuccess { .. })),
+               success);
+
+    // Once every client is gone, the game loop ends.
+    drop(clients);
+    server.join().unwrap().unwrap();
+}